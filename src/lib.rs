@@ -1,12 +1,14 @@
 use proc_macro::TokenStream;
 use proc_macro2::TokenStream as TokenStream2;
-use quote::quote;
+use quote::{format_ident, quote, ToTokens};
 use syn::punctuated::Punctuated;
 use syn::token::Comma;
-use syn::{parse_macro_input, Attribute, Data, DeriveInput, Expr, Fields, Lit, Meta};
+use syn::{
+    parse_macro_input, Attribute, Data, DeriveInput, Expr, ExprLit, Fields, Generics, Ident, Index,
+    Lit, Member, Meta, Type,
+};
 
 // 定义配置选项结构体
-#[derive(Default)]
 struct DataclassOptions {
     init: bool,
     repr: bool,
@@ -15,9 +17,77 @@ struct DataclassOptions {
     unsafe_hash: bool,
     frozen: bool,
     match_args: bool,
+    // Rust has no positional/keyword split at call sites, so unlike Python
+    // there is no rule that keyword-only fields follow positional ones
     kw_only: bool,
     slots: bool,
     weakref_slot: bool,
+    newtype: bool,
+    functional: bool,
+    strict_eq_hash: bool,
+    inline_getters: bool,
+    index: bool,
+    merge: bool,
+    env: bool,
+    hash_subset: bool,
+    repr_style: ReprStyle,
+    non_exhaustive: bool,
+    const_defaults: bool,
+    runtime_redact: bool,
+    serde_strict: bool,
+    partial_eq_only: bool,
+    force_eq: bool,
+    view: bool,
+    debug_use_serde_names: bool,
+    hash_type_tag: bool,
+    default: bool,
+    default_panic: bool,
+    field_enum: bool,
+    deref: bool,
+    // `Deref::Target`, defaulting to the field type
+    deref_target: Option<Type>,
+    // serde container attributes injected next to the serde derive
+    serde_tag: Option<String>,
+    serde_rename_all: Option<String>,
+    // delimiter for `TryFrom<&str>` record parsing
+    from_csv_row: Option<String>,
+    track_changes: bool,
+    cache_hash: bool,
+    bool_ops: bool,
+    properties: bool,
+    schema: bool,
+    arbitrary: bool,
+    mock: bool,
+    derive_eq_from_ord: bool,
+    add_assign: bool,
+    zero: bool,
+    debug_address: bool,
+    borrow_str: bool,
+    from_pairs: bool,
+    transparent: bool,
+    matches: bool,
+    diff: bool,
+    debug_with: bool,
+    json_value: bool,
+    into_json: bool,
+    index_by_name: bool,
+    serialize_only: bool,
+    typed_get: bool,
+    stable_hash: bool,
+    apply_line: bool,
+    assert_size: Option<u32>,
+    default_const: bool,
+    // operator impls for numeric newtypes: "add", "sub", "mul"
+    arithmetic: Vec<String>,
+}
+
+// output format of the generated Debug impl
+#[derive(PartialEq)]
+enum ReprStyle {
+    Rust,
+    Json,
+    // the user writes Debug; everything built on it is still generated
+    Custom,
 }
 
 impl DataclassOptions {
@@ -33,32 +103,237 @@ impl DataclassOptions {
             kw_only: false,
             slots: false,
             weakref_slot: false,
+            newtype: false,
+            functional: false,
+            strict_eq_hash: false,
+            inline_getters: false,
+            index: false,
+            merge: false,
+            env: false,
+            hash_subset: false,
+            repr_style: ReprStyle::Rust,
+            non_exhaustive: false,
+            const_defaults: false,
+            runtime_redact: false,
+            serde_strict: false,
+            partial_eq_only: false,
+            force_eq: false,
+            view: false,
+            debug_use_serde_names: false,
+            hash_type_tag: false,
+            default: false,
+            default_panic: false,
+            field_enum: false,
+            deref: false,
+            deref_target: None,
+            serde_tag: None,
+            serde_rename_all: None,
+            from_csv_row: None,
+            track_changes: false,
+            cache_hash: false,
+            bool_ops: false,
+            properties: false,
+            schema: false,
+            arbitrary: false,
+            mock: false,
+            derive_eq_from_ord: false,
+            add_assign: false,
+            zero: false,
+            debug_address: false,
+            borrow_str: false,
+            from_pairs: false,
+            transparent: false,
+            matches: false,
+            diff: false,
+            debug_with: false,
+            json_value: false,
+            into_json: false,
+            index_by_name: false,
+            serialize_only: false,
+            typed_get: false,
+            stable_hash: false,
+            apply_line: false,
+            assert_size: None,
+            default_const: false,
+            arithmetic: Vec::new(),
         };
 
         for meta in meta_list {
             match meta {
                 Meta::NameValue(nv) => {
                     if let Some(ident) = nv.path.get_ident() {
-                        let value = match nv.value {
-                            Expr::Lit(expr_lit) => match expr_lit.lit {
-                                Lit::Bool(lit_bool) => lit_bool.value(),
-                                _ => panic!("Expected boolean value for option {}", ident),
+                        match ident.to_string().as_str() {
+                            "eq_hash_consistency" => {
+                                options.strict_eq_hash =
+                                    match str_value(&nv.value, ident).as_str() {
+                                        "strict" => true,
+                                        "lenient" => false,
+                                        other => panic!(
+                                            "Expected \"strict\" or \"lenient\" for eq_hash_consistency, got {:?}",
+                                            other
+                                        ),
+                                    }
+                            }
+                            // presets set several options at once; options
+                            // listed after the preset still override it
+                            "preset" => match str_value(&nv.value, ident).as_str() {
+                                "frozen_entity" => {
+                                    options.frozen = true;
+                                    options.eq = true;
+                                    options.order = true;
+                                    options.unsafe_hash = true;
+                                }
+                                "ordered" => {
+                                    options.eq = true;
+                                    options.order = true;
+                                }
+                                "hashable" => {
+                                    options.eq = true;
+                                    options.unsafe_hash = true;
+                                }
+                                other => panic!("Unknown preset: {:?}", other),
                             },
-                            _ => panic!("Expected literal value for option {}", ident),
-                        };
+                            "repr_style" => {
+                                options.repr_style = match str_value(&nv.value, ident).as_str() {
+                                    "rust" => ReprStyle::Rust,
+                                    "json" => ReprStyle::Json,
+                                    "custom" => ReprStyle::Custom,
+                                    other => panic!(
+                                        "Expected \"rust\", \"json\" or \"custom\" for repr_style, got {:?}",
+                                        other
+                                    ),
+                                }
+                            }
+                            "serde_tag" => options.serde_tag = Some(str_value(&nv.value, ident)),
+                            "assert_size" => options.assert_size = Some(u32_value(&nv.value, ident)),
+                            "serde_rename_all" => {
+                                options.serde_rename_all = Some(str_value(&nv.value, ident))
+                            }
+                            "from_csv_row" => {
+                                let delimiter = str_value(&nv.value, ident);
+                                if delimiter.is_empty() {
+                                    panic!("from_csv_row requires a non-empty delimiter");
+                                }
+                                options.from_csv_row = Some(delimiter);
+                            }
+                            "deref_target" => {
+                                let target = syn::parse2(nv.value.to_token_stream())
+                                    .unwrap_or_else(|err| {
+                                        panic!("Expected a type for deref_target: {}", err)
+                                    });
+                                options.deref = true;
+                                options.deref_target = Some(target);
+                            }
+                            // `arithmetic = true` enables every operator,
+                            // `arithmetic = "add,sub"` only the listed ones
+                            "arithmetic" => {
+                                let ops = match &nv.value {
+                                    Expr::Lit(ExprLit {
+                                        lit: Lit::Bool(lit_bool),
+                                        ..
+                                    }) => {
+                                        if lit_bool.value() {
+                                            "add,sub,mul".to_string()
+                                        } else {
+                                            String::new()
+                                        }
+                                    }
+                                    value => str_value(value, ident),
+                                };
+                                options.arithmetic = ops
+                                    .split(',')
+                                    .map(|op| op.trim().to_string())
+                                    .filter(|op| !op.is_empty())
+                                    .collect();
+                                for op in &options.arithmetic {
+                                    if !matches!(op.as_str(), "add" | "sub" | "mul") {
+                                        panic!("Unknown arithmetic operator: {:?}", op);
+                                    }
+                                }
+                            }
+                            name => {
+                                let value = bool_value(&nv.value, ident);
 
-                        match ident.to_string().as_str() {
-                            "init" => options.init = value,
-                            "repr" => options.repr = value,
-                            "eq" => options.eq = value,
-                            "order" => options.order = value,
-                            "unsafe_hash" => options.unsafe_hash = value,
-                            "kw_only" => options.kw_only = value,
-                            "slots" => options.slots = value,
-                            "frozen" => options.frozen = value,
-                            "match_args" => options.match_args = value,
-                            "weakref_slot" => options.weakref_slot = value,
-                            _ => panic!("Unknown option: {}", ident),
+                                match name {
+                                    "init" => options.init = value,
+                                    "repr" => options.repr = value,
+                                    "eq" => options.eq = value,
+                                    "order" => options.order = value,
+                                    "unsafe_hash" => options.unsafe_hash = value,
+                                    "kw_only" => options.kw_only = value,
+                                    "slots" => options.slots = value,
+                                    "frozen" => options.frozen = value,
+                                    "match_args" => options.match_args = value,
+                                    "weakref_slot" => options.weakref_slot = value,
+                                    "newtype" => options.newtype = value,
+                                    "functional" => options.functional = value,
+                                    "inline_getters" => options.inline_getters = value,
+                                    "index" => options.index = value,
+                                    "merge" => options.merge = value,
+                                    "env" => options.env = value,
+                                    "hash_subset" => options.hash_subset = value,
+                                    "non_exhaustive" => options.non_exhaustive = value,
+                                    "const_defaults" => options.const_defaults = value,
+                                    "runtime_redact" => options.runtime_redact = value,
+                                    "serde_strict" | "serde_deny_unknown" => {
+                                        options.serde_strict = value
+                                    }
+                                    "partial_eq_only" => options.partial_eq_only = value,
+                                    "force_eq" => options.force_eq = value,
+                                    "view" => options.view = value,
+                                    "debug_use_serde_names" => {
+                                        options.debug_use_serde_names = value
+                                    }
+                                    "hash_type_tag" => options.hash_type_tag = value,
+                                    "default" => options.default = value,
+                                    "default_panic" => options.default_panic = value,
+                                    "default_const" => options.default_const = value,
+                                    "field_enum" => options.field_enum = value,
+                                    "deref" => options.deref = value,
+                                    "track_changes" => options.track_changes = value,
+                                    "cache_hash" => options.cache_hash = value,
+                                    "bool_ops" => options.bool_ops = value,
+                                    "properties" => options.properties = value,
+                                    "schema" => options.schema = value,
+                                    "arbitrary" => options.arbitrary = value,
+                                    "mock" => options.mock = value,
+                                    "derive_eq_from_ord" => options.derive_eq_from_ord = value,
+                                    "add_assign" => options.add_assign = value,
+                                    "zero" => options.zero = value,
+                                    "debug_address" => options.debug_address = value,
+                                    "borrow_str" => options.borrow_str = value,
+                                    "from_pairs" => options.from_pairs = value,
+                                    "transparent" => options.transparent = value,
+                                    "matches" => options.matches = value,
+                                    "diff" => options.diff = value,
+                                    "debug_with" => options.debug_with = value,
+                                    "json_value" => options.json_value = value,
+                                    "into_json" => options.into_json = value,
+                                    "index_by_name" => options.index_by_name = value,
+                                    "serialize_only" => options.serialize_only = value,
+                                    "typed_get" => options.typed_get = value,
+                                    "stable_hash" => options.stable_hash = value,
+                                    "apply_line" => options.apply_line = value,
+                                    _ => panic!("Unknown option: {}", ident),
+                                }
+                            }
+                        }
+                    }
+                }
+                // grouped form: `derive(eq, order, hash)` enables the listed impls
+                Meta::List(list) if list.path.is_ident("derive") => {
+                    let names = list
+                        .parse_args_with(Punctuated::<Ident, Comma>::parse_terminated)
+                        .unwrap_or_else(|err| panic!("Invalid derive list: {}", err));
+
+                    for name in names {
+                        match name.to_string().as_str() {
+                            "init" => options.init = true,
+                            "repr" => options.repr = true,
+                            "eq" => options.eq = true,
+                            "order" => options.order = true,
+                            "hash" | "unsafe_hash" => options.unsafe_hash = true,
+                            _ => panic!("Unknown derive option: {}", name),
                         }
                     }
                 }
@@ -70,6 +345,390 @@ impl DataclassOptions {
     }
 }
 
+fn bool_value(expr: &Expr, ident: &Ident) -> bool {
+    match expr {
+        Expr::Lit(expr_lit) => match &expr_lit.lit {
+            Lit::Bool(lit_bool) => lit_bool.value(),
+            _ => panic!("Expected boolean value for option {}", ident),
+        },
+        _ => panic!("Expected literal value for option {}", ident),
+    }
+}
+
+fn str_value(expr: &Expr, ident: &Ident) -> String {
+    match expr {
+        Expr::Lit(expr_lit) => match &expr_lit.lit {
+            Lit::Str(lit_str) => lit_str.value(),
+            _ => panic!("Expected string value for option {}", ident),
+        },
+        _ => panic!("Expected literal value for option {}", ident),
+    }
+}
+
+fn u32_value(expr: &Expr, ident: &Ident) -> u32 {
+    match expr {
+        Expr::Lit(expr_lit) => match &expr_lit.lit {
+            Lit::Int(lit_int) => lit_int
+                .base10_parse()
+                .unwrap_or_else(|err| panic!("Invalid integer for option {}: {}", ident, err)),
+            _ => panic!("Expected integer value for option {}", ident),
+        },
+        _ => panic!("Expected literal value for option {}", ident),
+    }
+}
+
+// per-field options from `#[field(...)]`
+#[derive(Default)]
+struct FieldOptions {
+    compute: Option<Expr>,
+    sort_key: bool,
+    reverse: bool,
+    flatten: bool,
+    by_ptr: bool,
+    // compare, order and hash `*field`, for smart pointers whose own impls
+    // are not content-based
+    deref_eq: bool,
+    // left out of eq but ordered after every compared field
+    tiebreak: bool,
+    compare: Option<bool>,
+    compare_with: Option<Expr>,
+    // tolerance for float fields, or elementwise for float collections
+    approx_eq: Option<Expr>,
+    hash: Option<bool>,
+    validate_range: Option<Expr>,
+    non_empty: bool,
+    // `#[serde(skip)]` without leaving comparison the way `transient` does
+    skip: bool,
+    // `fn(&T) -> Result<(), String>`, also run by the builder setter
+    validate: Option<Expr>,
+    added_in: Option<String>,
+    merge_skip: bool,
+    id: bool,
+    // compared by `matches()`
+    key: bool,
+    // in-memory only: out of eq, hash and serde
+    transient: bool,
+    // composite hash key; implies a Hash impl over just these fields
+    hash_key: bool,
+    sensitive: bool,
+    lock: bool,
+    tag: Option<u32>,
+    getter_mut: bool,
+    none_last: bool,
+    intern: bool,
+    debug_truncate: Option<u32>,
+    // `#[field(clone = deep)]` copies the value behind an `Rc`/`Arc`
+    deep_clone: bool,
+    // `Some(false)` leaves the field out of Debug
+    repr: Option<bool>,
+    doc: Option<String>,
+    // `Some(None)` for the bare `default` flag
+    default: Option<Option<Expr>>,
+    env: Option<String>,
+}
+
+impl FieldOptions {
+    fn from_attrs(attrs: &[Attribute]) -> Self {
+        let mut options = FieldOptions::default();
+
+        for attr in attrs.iter().filter(|attr| attr.path().is_ident("field")) {
+            let meta_list = attr
+                .parse_args_with(Punctuated::<Meta, Comma>::parse_terminated)
+                .unwrap_or_else(|err| panic!("Invalid field attribute: {}", err));
+
+            for meta in meta_list {
+                match meta {
+                    Meta::NameValue(nv) => {
+                        let ident = nv
+                            .path
+                            .get_ident()
+                            .unwrap_or_else(|| panic!("Expected field option name"));
+
+                        match ident.to_string().as_str() {
+                            "compute" => options.compute = Some(nv.value),
+                            "compare" => options.compare = Some(bool_value(&nv.value, ident)),
+                            "compare_with" => options.compare_with = Some(nv.value),
+                            "approx_eq" => options.approx_eq = Some(nv.value),
+                            "hash" => options.hash = Some(bool_value(&nv.value, ident)),
+                            "repr" => options.repr = Some(bool_value(&nv.value, ident)),
+                            "default" => options.default = Some(Some(nv.value)),
+                            "env" => options.env = Some(str_value(&nv.value, ident)),
+                            "doc" => options.doc = Some(str_value(&nv.value, ident)),
+                            "tag" => options.tag = Some(u32_value(&nv.value, ident)),
+                            "clone" => {
+                                let mode = match &nv.value {
+                                    Expr::Path(expr_path) => match expr_path.path.get_ident() {
+                                        Some(mode) => mode.to_string(),
+                                        None => panic!("Expected shallow or deep for clone"),
+                                    },
+                                    value => str_value(value, ident),
+                                };
+                                options.deep_clone = match mode.as_str() {
+                                    "deep" => true,
+                                    "shallow" => false,
+                                    other => panic!(
+                                        "Expected shallow or deep for clone, got {:?}",
+                                        other
+                                    ),
+                                };
+                            }
+                            "debug_truncate" => {
+                                options.debug_truncate = Some(u32_value(&nv.value, ident))
+                            }
+                            "added_in" => options.added_in = Some(str_value(&nv.value, ident)),
+                            "validate" => options.validate = Some(nv.value),
+                            "validate_range" => match nv.value {
+                                Expr::Range(_) => options.validate_range = Some(nv.value),
+                                _ => panic!("Expected a range for validate_range"),
+                            },
+                            _ => panic!("Unknown field option: {}", ident),
+                        }
+                    }
+                    Meta::Path(path) => {
+                        let ident = path
+                            .get_ident()
+                            .unwrap_or_else(|| panic!("Expected field option name"));
+
+                        match ident.to_string().as_str() {
+                            "sort_key" => options.sort_key = true,
+                            "reverse" => options.reverse = true,
+                            "flatten" => options.flatten = true,
+                            "by_ptr" => options.by_ptr = true,
+                            "deref_eq" => options.deref_eq = true,
+                            "tiebreak" => {
+                                options.tiebreak = true;
+                                options.compare = Some(false);
+                            }
+                            "merge_skip" => options.merge_skip = true,
+                            "id" => options.id = true,
+                            "hash_key" => options.hash_key = true,
+                            "key" => options.key = true,
+                            "non_empty" => options.non_empty = true,
+                            "skip" => options.skip = true,
+                            "transient" => {
+                                options.transient = true;
+                                options.compare = Some(false);
+                                options.hash = Some(false);
+                            }
+                            "sensitive" => options.sensitive = true,
+                            "lock" => options.lock = true,
+                            "getter_mut" => options.getter_mut = true,
+                            "none_last" => options.none_last = true,
+                            "intern" => options.intern = true,
+                            "default" => options.default = Some(None),
+                            _ => panic!("Unknown field option: {}", ident),
+                        }
+                    }
+                    _ => panic!("Expected name = value pair or flag in field attribute"),
+                }
+            }
+        }
+
+        options
+    }
+
+    // the declared default, falling back to `Default::default()`
+    fn default_value(&self) -> TokenStream2 {
+        match &self.default {
+            Some(Some(expr)) => quote! { #expr },
+            _ => quote! { Default::default() },
+        }
+    }
+
+    // compare = false drops the field from eq and ordering
+    fn compared(&self) -> bool {
+        self.compare.unwrap_or(true)
+    }
+
+    // hashing follows compare unless set explicitly
+    fn hashed(&self) -> bool {
+        self.hash.unwrap_or_else(|| self.compared())
+    }
+}
+
+// last path segment of a type, e.g. `Vec` for `std::vec::Vec<String>`
+fn type_ident(ty: &Type) -> Option<&Ident> {
+    match ty {
+        Type::Path(type_path) if type_path.qself.is_none() => {
+            type_path.path.segments.last().map(|segment| &segment.ident)
+        }
+        _ => None,
+    }
+}
+
+// whether `f32`/`f64` appears anywhere in the type, e.g. `Option<f64>`
+fn contains_float(tokens: TokenStream2) -> bool {
+    tokens.into_iter().any(|token| match token {
+        proc_macro2::TokenTree::Ident(ident) => ident == "f32" || ident == "f64",
+        proc_macro2::TokenTree::Group(group) => contains_float(group.stream()),
+        _ => false,
+    })
+}
+
+// first generic type argument of the last path segment, e.g. `T` in `Mutex<T>`
+fn type_argument(ty: &Type) -> Option<&Type> {
+    match ty {
+        Type::Path(type_path) => match &type_path.path.segments.last()?.arguments {
+            syn::PathArguments::AngleBracketed(args) => {
+                args.args.iter().find_map(|arg| match arg {
+                    syn::GenericArgument::Type(ty) => Some(ty),
+                    _ => None,
+                })
+            }
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+// best-effort JSON Schema for a field type; unknown types accept anything
+fn schema_type(ty: &Type) -> String {
+    if let Type::Reference(reference) = ty {
+        return schema_type(&reference.elem);
+    }
+    if let Type::Array(array) = ty {
+        return format!(
+            "{{\"type\":\"array\",\"items\":{}}}",
+            schema_type(&array.elem)
+        );
+    }
+    let kind = type_ident(ty).map(|ident| ident.to_string());
+    let simple = match kind.as_deref() {
+        Some("i8") | Some("i16") | Some("i32") | Some("i64") | Some("i128") | Some("isize")
+        | Some("u8") | Some("u16") | Some("u32") | Some("u64") | Some("u128") | Some("usize") => {
+            "integer"
+        }
+        Some("f32") | Some("f64") => "number",
+        Some("String") | Some("str") | Some("char") => "string",
+        Some("bool") => "boolean",
+        Some("HashMap") | Some("BTreeMap") => "object",
+        Some("Vec") | Some("VecDeque") | Some("HashSet") | Some("BTreeSet") => {
+            let items = type_argument(ty).map_or_else(|| "{}".to_string(), schema_type);
+            return format!("{{\"type\":\"array\",\"items\":{}}}", items);
+        }
+        Some("Option") => {
+            let inner = type_argument(ty).map_or_else(|| "{}".to_string(), schema_type);
+            return format!("{{\"anyOf\":[{},{{\"type\":\"null\"}}]}}", inner);
+        }
+        Some("Box") | Some("Rc") | Some("Arc") => {
+            return type_argument(ty).map_or_else(|| "{}".to_string(), schema_type);
+        }
+        _ => return "{}".to_string(),
+    };
+    format!("{{\"type\":\"{}\"}}", simple)
+}
+
+// whether `ident` appears anywhere in the tokens, e.g. `T` in `Vec<T>`
+fn mentions(tokens: TokenStream2, ident: &Ident) -> bool {
+    tokens.into_iter().any(|token| match token {
+        proc_macro2::TokenTree::Ident(other) => other == *ident,
+        proc_macro2::TokenTree::Group(group) => mentions(group.stream(), ident),
+        _ => false,
+    })
+}
+
+// adds `T: #bound` for every type parameter not in `skip`, and
+// `T::Item: #bound` for associated-type projections used by the fields
+fn with_bounds(
+    generics: &Generics,
+    bound: TokenStream2,
+    skip: &[Ident],
+    projections: &[&Type],
+) -> Generics {
+    let mut generics = generics.clone();
+    let params: Vec<_> = generics
+        .type_params()
+        .map(|param| param.ident.clone())
+        .filter(|param| !skip.contains(param))
+        .collect();
+    let where_clause = generics.make_where_clause();
+    for param in params {
+        where_clause
+            .predicates
+            .push(syn::parse_quote!(#param: #bound));
+    }
+    for ty in projections {
+        where_clause.predicates.push(syn::parse_quote!(#ty: #bound));
+    }
+    generics
+}
+
+// associated-type projections of the type parameters inside a field type,
+// e.g. `T::Item` in `Vec<T::Item>` or `<T as Iterator>::Item`
+fn projections<'a>(ty: &'a Type, params: &[Ident], out: &mut Vec<&'a Type>) {
+    match ty {
+        Type::Path(type_path) => {
+            let rooted = match &type_path.qself {
+                Some(qself) => params
+                    .iter()
+                    .any(|param| mentions(qself.ty.to_token_stream(), param)),
+                None => {
+                    type_path.path.segments.len() > 1
+                        && params
+                            .iter()
+                            .any(|param| type_path.path.segments[0].ident == *param)
+                }
+            };
+            if rooted {
+                if !out.contains(&ty) {
+                    out.push(ty);
+                }
+                return;
+            }
+            for segment in &type_path.path.segments {
+                if let syn::PathArguments::AngleBracketed(args) = &segment.arguments {
+                    for arg in &args.args {
+                        if let syn::GenericArgument::Type(ty) = arg {
+                            projections(ty, params, out);
+                        }
+                    }
+                }
+            }
+        }
+        Type::Reference(reference) => projections(&reference.elem, params, out),
+        Type::Array(array) => projections(&array.elem, params, out),
+        Type::Slice(slice) => projections(&slice.elem, params, out),
+        Type::Tuple(tuple) => {
+            for elem in &tuple.elems {
+                projections(elem, params, out);
+            }
+        }
+        _ => {}
+    }
+}
+
+// `"2.1"` -> `[2, 1]`, so versions compare numerically
+fn version_key(version: &str) -> Vec<u64> {
+    version
+        .split('.')
+        .map(|part| {
+            part.parse()
+                .unwrap_or_else(|_| panic!("Invalid version in added_in: {:?}", version))
+        })
+        .collect()
+}
+
+// `0..150` rather than the token spacing of `0 .. 150`
+fn range_text(expr: &Expr) -> String {
+    match expr {
+        Expr::Range(range) => {
+            let start = range.start.as_ref().map(|start| quote!(#start).to_string());
+            let end = range.end.as_ref().map(|end| quote!(#end).to_string());
+            let limits = match range.limits {
+                syn::RangeLimits::HalfOpen(_) => "..",
+                syn::RangeLimits::Closed(_) => "..=",
+            };
+            format!(
+                "{}{}{}",
+                start.unwrap_or_default(),
+                limits,
+                end.unwrap_or_default()
+            )
+        }
+        _ => quote!(#expr).to_string(),
+    }
+}
+
 fn has_serde_attribute(attrs: &[Attribute]) -> bool {
     attrs.iter().any(|attr| {
         if let Ok(Meta::Path(path)) = attr.parse_args::<Meta>() {
@@ -80,155 +739,3201 @@ fn has_serde_attribute(attrs: &[Attribute]) -> bool {
     })
 }
 
+// the `rename = "..."` of a field's `#[serde(...)]` attributes
+fn serde_rename(attrs: &[Attribute]) -> Option<String> {
+    attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("serde"))
+        .filter_map(|attr| {
+            attr.parse_args_with(Punctuated::<Meta, Comma>::parse_terminated)
+                .ok()
+        })
+        .flatten()
+        .find_map(|meta| match meta {
+            Meta::NameValue(nv) if nv.path.is_ident("rename") => match nv.value {
+                Expr::Lit(expr_lit) => match expr_lit.lit {
+                    Lit::Str(lit_str) => Some(lit_str.value()),
+                    _ => None,
+                },
+                _ => None,
+            },
+            _ => None,
+        })
+}
+
+// whether a `#[serde(...)]` attribute already sets `option`
+fn has_serde_container_option(attrs: &[Attribute], option: &str) -> bool {
+    attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("serde"))
+        .filter_map(|attr| {
+            attr.parse_args_with(Punctuated::<Meta, Comma>::parse_terminated)
+                .ok()
+        })
+        .flatten()
+        .any(|meta| meta.path().is_ident(option))
+}
+
+/// Python-style dataclass generation for structs.
+///
+/// With `eq_hash_consistency = "strict"` every field must be either both
+/// compared and hashed or neither:
+///
+/// ```compile_fail
+/// use dataclass_macro::dataclass;
+///
+/// #[dataclass(unsafe_hash = true, eq_hash_consistency = "strict")]
+/// struct User {
+///     id: u64,
+///     #[field(hash = false)]
+///     name: String,
+/// }
+/// ```
+///
+/// Structs with compared `f32`/`f64` fields implement `PartialEq` but not
+/// `Eq`, unless `force_eq = true`:
+///
+/// ```compile_fail
+/// use dataclass_macro::dataclass;
+///
+/// #[dataclass]
+/// struct Point {
+///     x: f64,
+/// }
+///
+/// fn assert_eq_impl<T: Eq>() {}
+/// assert_eq_impl::<Point>();
+/// ```
+///
+/// `assert_size` rejects a struct whose size is not the one given:
+///
+/// ```compile_fail
+/// use dataclass_macro::dataclass;
+///
+/// #[dataclass(assert_size = 12)]
+/// struct Header {
+///     id: u64,
+///     len: u32,
+///     flags: u32,
+/// }
+/// ```
 #[proc_macro_attribute]
 pub fn dataclass(args: TokenStream, input: TokenStream) -> TokenStream {
     let args =
         parse_macro_input!(args with syn::punctuated::Punctuated::<Meta, Comma>::parse_terminated);
-    let mut input = parse_macro_input!(input as DeriveInput);
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(args, input).into()
+}
 
+// the expansion on `proc_macro2` tokens, so unit tests can inspect it
+fn expand(args: Punctuated<Meta, Comma>, mut input: DeriveInput) -> TokenStream2 {
+    // enums only take the options that pick derives; anything else would be
+    // ignored without a word
+    let mut enum_errors = TokenStream2::new();
+    if let Data::Enum(_) = input.data {
+        const ENUM_OPTIONS: &[&str] = &[
+            "repr",
+            "repr_style",
+            "eq",
+            "order",
+            "unsafe_hash",
+            "preset",
+            "serialize_only",
+        ];
+        for meta in &args {
+            let path = meta.path();
+            if !ENUM_OPTIONS.iter().any(|name| path.is_ident(name)) {
+                let message = format!("`{}` is not supported on enums", path.to_token_stream());
+                enum_errors.extend(syn::Error::new_spanned(path, message).to_compile_error());
+            }
+            // enums only choose between the derived Debug and none
+            if let Meta::NameValue(nv) = meta {
+                if nv.path.is_ident("repr_style")
+                    && str_value(&nv.value, nv.path.get_ident().unwrap()) == "json"
+                {
+                    let message = "repr_style = \"json\" is not supported on enums";
+                    enum_errors
+                        .extend(syn::Error::new_spanned(&nv.value, message).to_compile_error());
+                }
+            }
+        }
+    }
     let options = DataclassOptions::from_meta_list(args);
 
     // check if serde attribute is already present
     if !has_serde_attribute(&input.attrs) {
-        // add serde derive attribute
-        input.attrs.push(syn::parse_quote!(
-            #[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
-        ));
+        // add serde derive attribute; serialize_only leaves out Deserialize so
+        // write-only types may hold fields that cannot be deserialized
+        if options.serialize_only {
+            input.attrs.push(syn::parse_quote!(
+                #[cfg_attr(feature = "serde", derive(::serde::Serialize))]
+            ));
+        } else {
+            input.attrs.push(syn::parse_quote!(
+                #[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+            ));
+        }
     }
 
+    if let Data::Enum(_) = input.data {
+        let mut expanded = implement_enum(input, options);
+        expanded.extend(enum_errors);
+        return expanded;
+    }
     implement_dataclass(input, options)
 }
 
-fn implement_dataclass(input: DeriveInput, options: DataclassOptions) -> TokenStream {
+fn implement_dataclass(mut input: DeriveInput, mut options: DataclassOptions) -> TokenStream2 {
+    // track_changes adds a bookkeeping field, built like a computed field
+    if options.track_changes {
+        if options.frozen {
+            panic!("track_changes cannot be used on frozen structs");
+        }
+        match &mut input.data {
+            Data::Struct(syn::DataStruct {
+                fields: Fields::Named(fields_named),
+                ..
+            }) => fields_named.named.push(syn::parse_quote! {
+                #[field(compute = Vec::new(), compare = false)]
+                #[serde(skip)]
+                changed: Vec<&'static str>
+            }),
+            _ => panic!("track_changes requires a struct with named fields"),
+        }
+    }
+    // cache_hash stores the hash computed at construction; a zero (e.g. after
+    // serde skipped the field) means "not cached"
+    if options.cache_hash {
+        if !options.frozen || !options.unsafe_hash {
+            panic!("cache_hash requires frozen = true and unsafe_hash = true");
+        }
+        match &mut input.data {
+            Data::Struct(syn::DataStruct {
+                fields: Fields::Named(fields_named),
+                ..
+            }) => {
+                let names: Vec<_> = fields_named
+                    .named
+                    .iter()
+                    .map(|field| &field.ident)
+                    .collect();
+                let field: syn::Field = syn::parse_quote! {
+                    #[field(compute = Self::__cache_hash(#(&#names),*), compare = false)]
+                    #[serde(skip)]
+                    cached_hash: u64
+                };
+                fields_named.named.push(field);
+            }
+            _ => panic!("cache_hash requires a struct with named fields"),
+        }
+    }
+
     let struct_name = &input.ident;
     let attrs = &input.attrs;
 
-    let fields = match &input.data {
+    // struct-level `#[allow(...)]` also covers the generated impls, and a
+    // deprecated struct gets a deprecated constructor
+    let deprecated: Vec<_> = attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("deprecated"))
+        .collect();
+    let mut lint_attrs: Vec<_> = attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("allow"))
+        .cloned()
+        .collect();
+    if !deprecated.is_empty() {
+        lint_attrs.push(syn::parse_quote!(#[allow(deprecated)]));
+    }
+
+    let (fields, is_tuple) = match &input.data {
         Data::Struct(data_struct) => match &data_struct.fields {
-            Fields::Named(fields_named) => &fields_named.named,
-            _ => panic!("Dataclass only works with named fields"),
+            Fields::Named(fields_named) => (&fields_named.named, false),
+            Fields::Unnamed(fields_unnamed) => (&fields_unnamed.unnamed, true),
+            Fields::Unit => panic!("Dataclass does not work with unit structs"),
         },
         _ => panic!("Dataclass only works with structs"),
     };
 
-    let field_names: Vec<_> = fields
+    // `self.#members` accesses a field; `#field_names` names it as a
+    // constructor parameter (`field_0`, `field_1`, ... for tuple structs)
+    let members: Vec<Member> = fields
+        .iter()
+        .enumerate()
+        .map(|(i, field)| match &field.ident {
+            Some(ident) => Member::Named(ident.clone()),
+            None => Member::Unnamed(Index::from(i)),
+        })
+        .collect();
+    let field_names: Vec<Ident> = fields
         .iter()
-        .map(|field| field.ident.as_ref().unwrap())
+        .enumerate()
+        .map(|(i, field)| match &field.ident {
+            Some(ident) => ident.clone(),
+            None => format_ident!("field_{}", i),
+        })
         .collect();
     let field_types: Vec<_> = fields.iter().map(|field| &field.ty).collect();
+    let mut field_options: Vec<_> = fields
+        .iter()
+        .map(|field| FieldOptions::from_attrs(&field.attrs))
+        .collect();
+
+    // `PhantomData` markers are filled in by the constructors and left out of
+    // Debug, eq and hash unless their options say otherwise
+    let is_marker: Vec<bool> = fields
+        .iter()
+        .map(|field| matches!(type_ident(&field.ty), Some(ident) if ident == "PhantomData"))
+        .collect();
+    for (opts, &marker) in field_options.iter_mut().zip(&is_marker) {
+        if marker {
+            opts.default
+                .get_or_insert_with(|| Some(syn::parse_quote!(std::marker::PhantomData)));
+            opts.compare.get_or_insert(false);
+            opts.repr.get_or_insert(false);
+        }
+    }
+    if field_options.iter().any(|opts| opts.hash_key) || options.borrow_str || options.stable_hash {
+        options.unsafe_hash = true;
+    }
+    // fields the user declared, before the bookkeeping ones added above
+    let tracked = if options.track_changes || options.cache_hash {
+        field_options.last_mut().unwrap().repr = Some(false);
+        fields.len() - 1
+    } else {
+        fields.len()
+    };
+
+    // type parameters only used in markers need no trait bounds
+    let marker_params: Vec<Ident> = input
+        .generics
+        .type_params()
+        .map(|param| param.ident.clone())
+        .filter(|param| {
+            fields
+                .iter()
+                .zip(&is_marker)
+                .all(|(field, &marker)| marker || !mentions(field.ty.to_token_stream(), param))
+        })
+        .collect();
+    let type_params: Vec<Ident> = input
+        .generics
+        .type_params()
+        .map(|param| param.ident.clone())
+        .collect();
+    let mut projected = Vec::new();
+    for (field, &marker) in fields.iter().zip(&is_marker) {
+        if !marker {
+            projections(&field.ty, &type_params, &mut projected);
+        }
+    }
+
+    let generics = &input.generics;
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
 
     let mut implementations = TokenStream2::new();
 
+    // with track_changes, every generated method that assigns a field notes
+    // its name once in the change log
+    let record_change = |name: &Ident| {
+        if options.track_changes {
+            quote! {
+                if !self.changed.contains(&stringify!(#name)) {
+                    self.changed.push(stringify!(#name));
+                }
+            }
+        } else {
+            TokenStream2::new()
+        }
+    };
+
+    // with cache_hash, generated methods that assign a field store the new
+    // hash, so they live in impls bounded like the `Hash` impl
+    let refresh_hash = if options.cache_hash {
+        quote! { self.cached_hash = self.recompute_hash(); }
+    } else {
+        TokenStream2::new()
+    };
+    let mutator_generics = if options.cache_hash {
+        with_bounds(
+            generics,
+            quote!(std::hash::Hash),
+            &marker_params,
+            &projected,
+        )
+    } else {
+        generics.clone()
+    };
+    let (mutator_impl_generics, _, mutator_where_clause) = mutator_generics.split_for_impl();
+
+    // constructor parameters; computed, defaulted and later-version fields
+    // are not parameters of `new`
+    // `#[field(intern)]` fields take a `&str` that `new` interns
+    let ctor_types: Vec<TokenStream2> = field_types
+        .iter()
+        .zip(&field_options)
+        .map(|(ty, opts)| {
+            if opts.intern {
+                quote!(&str)
+            } else {
+                quote!(#ty)
+            }
+        })
+        .collect();
+    let (param_names, param_types): (Vec<_>, Vec<_>) = field_names
+        .iter()
+        .zip(&ctor_types)
+        .zip(&field_options)
+        .filter(|(_, opts)| {
+            opts.compute.is_none() && opts.added_in.is_none() && opts.default.is_none()
+        })
+        .map(|((name, ty), _)| (name, ty))
+        .unzip();
+
+    // every constructor binds its parameters and defaults first, then the
+    // computed fields in declaration order, and only then builds the struct
+    // with explicit `member: binding` pairs; a compute expression thus sees
+    // every parameter, every default and the computed fields above it
+    let computed: Vec<_> = field_names
+        .iter()
+        .zip(&field_types)
+        .zip(&field_options)
+        .filter_map(|((name, ty), opts)| {
+            opts.compute
+                .as_ref()
+                .map(|expr| quote! { let #name: #ty = #expr; })
+        })
+        .collect();
+
+    // items declared next to the struct rather than inside the const block
+    let mut companion_defs = TokenStream2::new();
+
     // (init option)
     if options.init {
-        let constructor = if options.kw_only {
-            quote! {
-                impl #struct_name {
-                    pub fn new(#(#field_names: #field_types),*) -> Self {
-                        Self {
-                            #(#field_names,)*
-                        }
+        // `new` keeps the original arity; every `added_in` version gets a
+        // `new_v<version>` taking the fields added up to that version
+        let mut versions: Vec<Vec<u64>> = field_options
+            .iter()
+            .filter_map(|opts| opts.added_in.as_deref().map(version_key))
+            .collect();
+        versions.sort();
+        versions.dedup();
+
+        let constructor = |fn_name: Ident, version: Option<&Vec<u64>>| {
+            let mut params = Vec::new();
+            let mut defaults = Vec::new();
+            for (((name, ty), ctor_ty), opts) in field_names
+                .iter()
+                .zip(&field_types)
+                .zip(&ctor_types)
+                .zip(&field_options)
+            {
+                if opts.compute.is_some() {
+                    continue;
+                }
+                let included = match (opts.added_in.as_deref(), version) {
+                    (Some(added), Some(version)) => version_key(added) <= *version,
+                    (Some(_), None) => false,
+                    (None, _) => opts.default.is_none(),
+                };
+                if included {
+                    params.push(quote! { #name: #ctor_ty });
+                    if opts.intern {
+                        defaults.push(quote! { let #name: #ty = __intern(#name); });
                     }
+                } else {
+                    let default = opts.default_value();
+                    defaults.push(quote! { let #name: #ty = #default; });
                 }
             }
-        } else {
+
             quote! {
-                impl #struct_name {
-                    pub fn new(#(#field_names: #field_types),*) -> Self {
-                        Self {
-                            #(#field_names,)*
-                        }
+                #(#deprecated)*
+                pub fn #fn_name(#(#params),*) -> Self {
+                    #(#defaults)*
+                    #(#computed)*
+                    Self {
+                        #(#members: #field_names,)*
                     }
                 }
             }
         };
-        implementations.extend(constructor);
-    }
 
-    // Debug (repr option)
-    if options.repr {
-        let debug_impl = quote! {
-            impl std::fmt::Debug for #struct_name {
-                fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-                    f.debug_struct(stringify!(#struct_name))
-                        #(.field(stringify!(#field_names), &self.#field_names))*
-                        .finish()
+        // one interner per type, shared by all of its `#[field(intern)]`
+        // fields; a const `Mutex::new` would need Rust 1.63, so the interner
+        // is leaked on first use and published through an `AtomicPtr`
+        let interned: Vec<_> = field_types
+            .iter()
+            .zip(&field_options)
+            .filter(|(_, opts)| opts.intern)
+            .map(|(ty, _)| *ty)
+            .collect();
+        for ty in &interned {
+            let is_arc = matches!(type_ident(ty), Some(ident) if ident == "Arc");
+            let is_str =
+                matches!(type_argument(ty).and_then(type_ident), Some(ident) if ident == "str");
+            if !is_arc || !is_str {
+                panic!("#[field(intern)] requires an Arc<str> field");
+            }
+        }
+        if !interned.is_empty() {
+            implementations.extend(quote! {
+                type __Interner = std::sync::Mutex<std::collections::HashSet<std::sync::Arc<str>>>;
+
+                static INTERNER: std::sync::atomic::AtomicPtr<__Interner> =
+                    std::sync::atomic::AtomicPtr::new(std::ptr::null_mut());
+
+                #[allow(unsafe_code)]
+                fn __interner() -> &'static __Interner {
+                    use std::sync::atomic::Ordering;
+
+                    let mut current = INTERNER.load(Ordering::Acquire);
+                    if current.is_null() {
+                        let fresh = Box::into_raw(Box::new(__Interner::default()));
+                        current = match INTERNER.compare_exchange(
+                            std::ptr::null_mut(),
+                            fresh,
+                            Ordering::AcqRel,
+                            Ordering::Acquire,
+                        ) {
+                            Ok(_) => fresh,
+                            Err(existing) => {
+                                // another thread won the race; ours was never shared
+                                drop(unsafe { Box::from_raw(fresh) });
+                                existing
+                            }
+                        };
+                    }
+                    // set once, never freed or replaced
+                    unsafe { &*current }
+                }
+
+                fn __intern(value: &str) -> std::sync::Arc<str> {
+                    let mut interner = __interner().lock().unwrap_or_else(|err| err.into_inner());
+                    if let Some(existing) = interner.get(value) {
+                        return existing.clone();
+                    }
+                    let interned: std::sync::Arc<str> = value.into();
+                    interner.insert(interned.clone());
+                    interned
                 }
+            });
+        }
+
+        let mut constructors = vec![constructor(format_ident!("new"), None)];
+        for version in &versions {
+            let suffix: Vec<_> = version.iter().map(|part| part.to_string()).collect();
+            let fn_name = format_ident!("new_v{}", suffix.join("_"));
+            constructors.push(constructor(fn_name, Some(version)));
+        }
+
+        let constructor_impl = quote! {
+            impl #impl_generics #struct_name #ty_generics #where_clause {
+                #(#constructors)*
             }
         };
-        implementations.extend(debug_impl);
-    }
+        implementations.extend(constructor_impl);
 
-    // (eq option)
-    if options.eq {
-        let eq_impl = quote! {
-            impl PartialEq for #struct_name {
-                fn eq(&self, other: &Self) -> bool {
-                    #(self.#field_names == other.#field_names)&&*
+        if options.kw_only {
+            // named arguments also go through `#struct_nameBuilder`
+            let builder_name = format_ident!("{}Builder", struct_name);
+            let settable: Vec<usize> = (0..fields.len())
+                .filter(|&i| field_options[i].compute.is_none())
+                .collect();
+            let names: Vec<_> = settable.iter().map(|&i| &field_names[i]).collect();
+            let types: Vec<_> = settable.iter().map(|&i| field_types[i]).collect();
+            let setters = settable.iter().map(|&i| {
+                let (name, ty, opts) = (&field_names[i], field_types[i], &field_options[i]);
+                let maybe_name = format_ident!("maybe_{}", name);
+                let (param_ty, value) = if opts.intern {
+                    (quote!(&str), quote!(__intern(value)))
+                } else {
+                    (quote!(#ty), quote!(value))
+                };
+                // validated fields reject a bad value as soon as it is set
+                if let Some(check) = &opts.validate {
+                    return quote! {
+                        pub fn #name(mut self, value: #param_ty) -> Result<Self, String> {
+                            let value = #value;
+                            (#check)(&value)
+                                .map_err(|err| format!("{}: {}", stringify!(#name), err))?;
+                            self.#name = Some(value);
+                            Ok(self)
+                        }
+
+                        pub fn #maybe_name(self, value: Option<#param_ty>) -> Result<Self, String> {
+                            match value {
+                                Some(value) => self.#name(value),
+                                None => Ok(self),
+                            }
+                        }
+                    };
+                }
+                quote! {
+                    pub fn #name(mut self, value: #param_ty) -> Self {
+                        self.#name = Some(#value);
+                        self
+                    }
+
+                    pub fn #maybe_name(mut self, value: Option<#param_ty>) -> Self {
+                        if let Some(value) = value {
+                            self.#name = Some(#value);
+                        }
+                        self
+                    }
+                }
+            });
+            let values = settable.iter().map(|&i| {
+                let (name, ty, opts) = (&field_names[i], field_types[i], &field_options[i]);
+                if opts.default.is_some() || opts.added_in.is_some() {
+                    let default = opts.default_value();
+                    quote! { let #name: #ty = self.#name.unwrap_or_else(|| #default); }
+                } else {
+                    let missing = format!("missing field `{}`", name);
+                    quote! { let #name: #ty = self.#name.ok_or(#missing)?; }
+                }
+            });
+
+            companion_defs.extend(quote! {
+                #(#lint_attrs)*
+                pub struct #builder_name #generics #where_clause {
+                    #(#names: Option<#types>,)*
+                    __marker: std::marker::PhantomData<#struct_name #ty_generics>,
+                }
+            });
+            implementations.extend(quote! {
+                impl #impl_generics #struct_name #ty_generics #where_clause {
+                    #(#deprecated)*
+                    pub fn builder() -> #builder_name #ty_generics {
+                        #builder_name {
+                            #(#names: None,)*
+                            __marker: std::marker::PhantomData,
+                        }
+                    }
+                }
+
+                impl #impl_generics #builder_name #ty_generics #where_clause {
+                    #(#setters)*
+
+                    pub fn build(self) -> Result<#struct_name #ty_generics, String> {
+                        #(#values)*
+                        #(#computed)*
+                        Ok(#struct_name {
+                            #(#members: #field_names,)*
+                        })
+                    }
                 }
+            });
+        }
+    }
+
+    // `const DEFAULTS: Self` (const_defaults option) and `const fn
+    // default_const()` backing `Default` (default_const option)
+    if options.const_defaults || options.default_const {
+        let option = if options.const_defaults {
+            "const_defaults"
+        } else {
+            "default_const"
+        };
+        let mut values = Vec::new();
+        for ((name, ty), opts) in field_names.iter().zip(&field_types).zip(&field_options) {
+            if opts.compute.is_some() {
+                continue;
+            }
+            match &opts.default {
+                Some(Some(expr)) => values.push(quote! { let #name: #ty = #expr; }),
+                _ => panic!(
+                    "{} requires a const #[field(default = ...)] expression on field {}",
+                    option, name
+                ),
             }
+        }
 
-            impl Eq for #struct_name {}
+        let body = quote! {
+            #(#values)*
+            #(#computed)*
+            Self {
+                #(#members: #field_names,)*
+            }
         };
-        implementations.extend(eq_impl);
+        if options.const_defaults {
+            implementations.extend(quote! {
+                impl #impl_generics #struct_name #ty_generics #where_clause {
+                    pub const DEFAULTS: Self = { #body };
+                }
+            });
+        }
+        if options.default_const {
+            implementations.extend(quote! {
+                impl #impl_generics #struct_name #ty_generics #where_clause {
+                    pub const fn default_const() -> Self {
+                        #body
+                    }
+                }
+
+                impl #impl_generics Default for #struct_name #ty_generics #where_clause {
+                    fn default() -> Self {
+                        Self::default_const()
+                    }
+                }
+            });
+        }
     }
 
-    // (order option)
-    if options.order {
-        let ord_impl = quote! {
-            impl PartialOrd for #struct_name {
-                fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-                    Some(self.cmp(other))
+    // `impl Default` from the field defaults (default option); with
+    // default_panic, fields without `#[field(default)]` panic when built,
+    // which is meant for test scaffolding; default_const writes its own impl
+    if (options.default || options.default_panic) && !options.default_const {
+        let values = field_names
+            .iter()
+            .zip(&field_types)
+            .zip(&field_options)
+            .filter(|(_, opts)| opts.compute.is_none())
+            .map(|((name, ty), opts)| {
+                let value = if options.default_panic && opts.default.is_none() {
+                    let message = format!("field `{}` has no default", name);
+                    quote! { __missing_default(#message) }
+                } else {
+                    opts.default_value()
+                };
+                quote! { let #name: #ty = #value; }
+            });
+        let missing_default = if options.default_panic {
+            quote! {
+                fn __missing_default<T>(message: &str) -> T {
+                    panic!("{}", message)
                 }
             }
+        } else {
+            TokenStream2::new()
+        };
 
-            impl Ord for #struct_name {
-                fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-                    #(
-                        if let std::cmp::Ordering::Equal = self.#field_names.cmp(&other.#field_names) {
-                        } else {
-                            return self.#field_names.cmp(&other.#field_names);
-                        }
-                    )*
-                    std::cmp::Ordering::Equal
+        let default_impl = quote! {
+            impl #impl_generics Default for #struct_name #ty_generics #where_clause {
+                fn default() -> Self {
+                    #missing_default
+                    #(#values)*
+                    #(#computed)*
+                    Self {
+                        #(#members: #field_names,)*
+                    }
                 }
             }
         };
-        implementations.extend(ord_impl);
+        implementations.extend(default_impl);
     }
 
-    // Hash (unsafe_hash option)
-    if options.unsafe_hash {
-        let hash_impl = quote! {
-            impl std::hash::Hash for #struct_name {
-                fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
-                    #(self.#field_names.hash(state);)*
+    // `mock()` with deterministic sample values for tests (mock option);
+    // `String` and `&str` fields get "test", everything else its default
+    if options.mock {
+        let values = field_names
+            .iter()
+            .zip(&field_types)
+            .zip(&field_options)
+            .filter(|(_, opts)| opts.compute.is_none())
+            .map(|((name, ty), opts)| {
+                let value = if opts.default.is_some() {
+                    opts.default_value()
+                } else {
+                    match ty {
+                        Type::Reference(reference)
+                            if reference.mutability.is_none()
+                                && matches!(type_ident(&reference.elem), Some(ident) if ident == "str") =>
+                        {
+                            quote! { "test" }
+                        }
+                        _ if matches!(type_ident(ty), Some(ident) if ident == "String") => {
+                            quote! { String::from("test") }
+                        }
+                        _ => quote! { Default::default() },
+                    }
+                };
+                quote! { let #name: #ty = #value; }
+            });
+        implementations.extend(quote! {
+            impl #impl_generics #struct_name #ty_generics #where_clause {
+                pub fn mock() -> Self {
+                    #(#values)*
+                    #(#computed)*
+                    Self {
+                        #(#members: #field_names,)*
+                    }
                 }
             }
-        };
-        implementations.extend(hash_impl);
+        });
     }
 
-    // (frozen option)
-    let struct_fields = if options.frozen {
-        quote! {
-            #(pub(crate) #field_names: #field_types,)*
+    // `arbitrary::Arbitrary` for fuzzing and property tests, compiled only
+    // when the user crate enables its `arbitrary` feature; fields with a
+    // `#[field(default)]` keep their default (arbitrary option)
+    if options.arbitrary {
+        let values = field_names
+            .iter()
+            .zip(&field_types)
+            .zip(&field_options)
+            .filter(|(_, opts)| opts.compute.is_none())
+            .map(|((name, ty), opts)| {
+                let value = if opts.default.is_some() {
+                    opts.default_value()
+                } else {
+                    quote! { ::arbitrary::Arbitrary::arbitrary(__u)? }
+                };
+                quote! { let #name: #ty = #value; }
+            });
+        let mut generics = with_bounds(
+            &input.generics,
+            quote!(::arbitrary::Arbitrary<'__a>),
+            &marker_params,
+            &projected,
+        );
+        generics.params.insert(0, syn::parse_quote!('__a));
+        let (impl_generics, _, where_clause) = generics.split_for_impl();
+        implementations.extend(quote! {
+            #[cfg(feature = "arbitrary")]
+            impl #impl_generics ::arbitrary::Arbitrary<'__a> for #struct_name #ty_generics #where_clause {
+                fn arbitrary(__u: &mut ::arbitrary::Unstructured<'__a>) -> ::arbitrary::Result<Self> {
+                    #(#values)*
+                    #(#computed)*
+                    Ok(Self {
+                        #(#members: #field_names,)*
+                    })
+                }
+            }
+        });
+    }
+
+    // `TryFrom<serde_json::Value>` reporting errors as `Struct.field: ...`,
+    // compiled only when the user crate enables its `serde_json` feature;
+    // skipped fields and missing fields with a default take the default
+    // (json_value option)
+    if options.json_value {
+        if is_tuple {
+            panic!("json_value requires a struct with named fields");
         }
-    } else {
+        let values = (0..fields.len())
+            .filter(|&i| field_options[i].compute.is_none())
+            .map(|i| {
+                let (name, ty, opts) = (&field_names[i], field_types[i], &field_options[i]);
+                let path = format!("{}.{}", struct_name, name);
+                let skipped = opts.transient
+                    || opts.skip
+                    || is_marker[i]
+                    || has_serde_container_option(&fields[i].attrs, "skip");
+                if skipped {
+                    let value = opts.default_value();
+                    return quote! { let #name: #ty = #value; };
+                }
+                let key = serde_rename(&fields[i].attrs).unwrap_or_else(|| name.to_string());
+                let missing = if opts.default.is_some() {
+                    opts.default_value()
+                } else {
+                    let message = format!("{}: missing field", path);
+                    quote! { return Err(#message.to_string()) }
+                };
+                quote! {
+                    let #name: #ty = match object.remove(#key) {
+                        Some(value) => ::serde_json::from_value(value)
+                            .map_err(|err| format!("{}: {}", #path, err))?,
+                        None => #missing,
+                    };
+                }
+            });
+        let not_object = format!("{}: expected an object, got {{}}", struct_name);
+        let generics = with_bounds(
+            &input.generics,
+            quote!(::serde::de::DeserializeOwned),
+            &marker_params,
+            &projected,
+        );
+        let (impl_generics, _, where_clause) = generics.split_for_impl();
+        implementations.extend(quote! {
+            #[cfg(feature = "serde_json")]
+            impl #impl_generics TryFrom<::serde_json::Value> for #struct_name #ty_generics #where_clause {
+                type Error = String;
+
+                fn try_from(value: ::serde_json::Value) -> Result<Self, Self::Error> {
+                    let mut object = match value {
+                        ::serde_json::Value::Object(object) => object,
+                        other => return Err(format!(#not_object, other)),
+                    };
+                    #(#values)*
+                    #(#computed)*
+                    Ok(Self {
+                        #(#members: #field_names,)*
+                    })
+                }
+            }
+        });
+    }
+
+    // Infallible `From<Struct> for serde_json::Value` for quick JSON
+    // construction, compiled only when the user crate enables its
+    // `serde_json` feature; the derived `Serialize` cannot fail for a
+    // struct, so the conversion panics only on a broken user impl
+    // (into_json option)
+    if options.into_json {
+        let message = format!("{} failed to serialize to JSON", struct_name);
+        let generics = with_bounds(
+            &input.generics,
+            quote!(::serde::Serialize),
+            &marker_params,
+            &projected,
+        );
+        let (impl_generics, _, where_clause) = generics.split_for_impl();
+        implementations.extend(quote! {
+            #[cfg(feature = "serde_json")]
+            impl #impl_generics From<#struct_name #ty_generics> for ::serde_json::Value #where_clause {
+                fn from(value: #struct_name #ty_generics) -> Self {
+                    ::serde_json::to_value(&value).expect(#message)
+                }
+            }
+        });
+    }
+
+    // compile-time check that the layout keeps its expected size, e.g. after
+    // a field is added (assert_size option)
+    if let Some(size) = options.assert_size {
+        if !input.generics.params.is_empty() {
+            panic!("assert_size requires a struct without generic parameters");
+        }
+        let size = size as usize;
+        let message = format!("size of {} is not {} bytes", struct_name, size);
+        implementations.extend(quote! {
+            const _: () = assert!(std::mem::size_of::<#struct_name>() == #size, #message);
+        });
+    }
+
+    // `PhantomData` markers have nothing to parse, so the text parsers below
+    // fill them in as the constructors do
+    let markers: Vec<_> = (0..fields.len())
+        .filter(|&i| is_marker[i] && field_options[i].compute.is_none())
+        .map(|i| {
+            let (name, ty) = (&field_names[i], field_types[i]);
+            let value = field_options[i].default_value();
+            quote! { let #name: #ty = #value; }
+        })
+        .collect();
+
+    // `apply_line("port = 8080")` parses one `.env`/ini-style line into the
+    // named field, so a config can be built up line by line; blank lines and
+    // `#` comments are accepted and ignored (apply_line option)
+    if options.apply_line {
+        if is_tuple {
+            panic!("apply_line requires a struct with named fields");
+        }
+        let arms = (0..tracked)
+            .filter(|&i| field_options[i].compute.is_none() && !is_marker[i])
+            .map(|i| {
+                let name = &field_names[i];
+                let key = name.to_string();
+                let invalid = format!("invalid value for field `{}`: ", name);
+                let record = record_change(name);
+                quote! {
+                    #key => {
+                        self.#name = value.parse().map_err(|err| format!("{}{}", #invalid, err))?;
+                        #record
+                    }
+                }
+            });
+        implementations.extend(quote! {
+            impl #mutator_impl_generics #struct_name #ty_generics #mutator_where_clause {
+                pub fn apply_line(&mut self, line: &str) -> Result<(), String> {
+                    let line = line.trim();
+                    if line.is_empty() || line.starts_with('#') {
+                        return Ok(());
+                    }
+                    let (key, value) = match line.split_once('=') {
+                        Some((key, value)) => (key.trim(), value.trim()),
+                        None => return Err(format!("expected `field=value`, got {:?}", line)),
+                    };
+                    match key {
+                        #(#arms)*
+                        other => return Err(format!("unknown field `{}`", other)),
+                    }
+                    #refresh_hash
+                    Ok(())
+                }
+            }
+        });
+    }
+
+    // `from_pairs(&[("name", "value")])` parses each value by field name for
+    // form and config data; fields with a default may be left out
+    // (from_pairs option)
+    if options.from_pairs {
+        if is_tuple {
+            panic!("from_pairs requires a struct with named fields");
+        }
+        let inputs: Vec<usize> = (0..fields.len())
+            .filter(|&i| field_options[i].compute.is_none() && !is_marker[i])
+            .collect();
+        let slots = inputs.iter().map(|&i| {
+            let (name, ty) = (&field_names[i], field_types[i]);
+            quote! { let mut #name: Option<#ty> = None; }
+        });
+        let arms = inputs.iter().map(|&i| {
+            let name = &field_names[i];
+            let key = name.to_string();
+            let invalid = format!("invalid value for field `{}`: ", name);
+            quote! {
+                #key => {
+                    #name = Some(value.parse().map_err(|err| format!("{}{}", #invalid, err))?);
+                }
+            }
+        });
+        let values = inputs.iter().map(|&i| {
+            let (name, ty) = (&field_names[i], field_types[i]);
+            let fallback = if field_options[i].default.is_some() {
+                field_options[i].default_value()
+            } else {
+                let missing = format!("missing field `{}`", name);
+                quote! { return Err(#missing.to_string()) }
+            };
+            quote! {
+                let #name: #ty = match #name {
+                    Some(value) => value,
+                    None => #fallback,
+                };
+            }
+        });
+        implementations.extend(quote! {
+            impl #impl_generics #struct_name #ty_generics #where_clause {
+                pub fn from_pairs(pairs: &[(&str, &str)]) -> Result<Self, String> {
+                    #(#slots)*
+                    for &(key, value) in pairs {
+                        match key {
+                            #(#arms)*
+                            other => return Err(format!("unknown field `{}`", other)),
+                        }
+                    }
+                    #(#values)*
+                    #(#markers)*
+                    #(#computed)*
+                    Ok(Self {
+                        #(#members: #field_names,)*
+                    })
+                }
+            }
+        });
+    }
+
+    // `TryFrom<&str>` splitting a delimited record into the non-computed
+    // fields in declaration order, and `to_csv_row` writing it back; values
+    // holding the delimiter, quotes or newlines are quoted CSV-style, with
+    // inner quotes doubled (from_csv_row option)
+    if let Some(delimiter) = &options.from_csv_row {
+        let columns: Vec<usize> = (0..fields.len())
+            .filter(|&i| field_options[i].compute.is_none() && !is_marker[i])
+            .collect();
+        let count = columns.len();
+        let column_members = columns.iter().map(|&i| &members[i]);
+        let values = columns.iter().enumerate().map(|(position, &i)| {
+            let (name, ty) = (&field_names[i], field_types[i]);
+            let missing = format!("missing field `{}` at position {}", name, position);
+            let invalid = format!(
+                "invalid value for field `{}` at position {}: ",
+                name, position
+            );
+            quote! {
+                let #name: #ty = parts
+                    .next()
+                    .ok_or(#missing)?
+                    .parse()
+                    .map_err(|err| format!("{}{}", #invalid, err))?;
+            }
+        });
+        let csv_impl = quote! {
+            impl #impl_generics TryFrom<&str> for #struct_name #ty_generics #where_clause {
+                type Error = String;
+
+                fn try_from(row: &str) -> Result<Self, Self::Error> {
+                    let mut parts = __split_record(row).into_iter();
+                    #(#values)*
+                    if parts.next().is_some() {
+                        return Err(format!("expected {} fields", #count));
+                    }
+                    #(#markers)*
+                    #(#computed)*
+                    Ok(Self {
+                        #(#members: #field_names,)*
+                    })
+                }
+            }
+
+            impl #impl_generics #struct_name #ty_generics #where_clause {
+                pub fn to_csv_row(&self) -> String {
+                    [#(__quote_value(self.#column_members.to_string())),*].join(#delimiter)
+                }
+            }
+
+            fn __quote_value(value: String) -> String {
+                if value.contains(#delimiter) || value.contains('"') || value.contains('\n') {
+                    format!("\"{}\"", value.replace('"', "\"\""))
+                } else {
+                    value
+                }
+            }
+
+            fn __split_record(row: &str) -> Vec<String> {
+                let mut values = Vec::new();
+                let mut value = String::new();
+                let mut quoted = false;
+                let mut rest = row;
+                while let Some(c) = rest.chars().next() {
+                    if quoted {
+                        if rest.starts_with("\"\"") {
+                            value.push('"');
+                            rest = &rest[2..];
+                            continue;
+                        }
+                        if c == '"' {
+                            quoted = false;
+                            rest = &rest[1..];
+                            continue;
+                        }
+                    } else if c == '"' && value.is_empty() {
+                        quoted = true;
+                        rest = &rest[1..];
+                        continue;
+                    } else if rest.starts_with(#delimiter) {
+                        values.push(std::mem::take(&mut value));
+                        rest = &rest[#delimiter.len()..];
+                        continue;
+                    }
+                    value.push(c);
+                    rest = &rest[c.len_utf8()..];
+                }
+                values.push(value);
+                values
+            }
+        };
+        implementations.extend(csv_impl);
+    }
+
+    // (env option)
+    if options.env {
+        let values: Vec<_> = fields
+            .iter()
+            .zip(&field_names)
+            .zip(&field_options)
+            .filter(|(_, opts)| opts.compute.is_none())
+            .map(|((field, name), opts)| {
+                let ty = &field.ty;
+                let value = match &opts.env {
+                    Some(var) => {
+                        let missing = if opts.default.is_some() {
+                            opts.default_value()
+                        } else {
+                            quote! {
+                                return Err(format!("environment variable {} is not set", #var))
+                            }
+                        };
+                        quote! {
+                            match std::env::var(#var) {
+                                Ok(value) => value.parse().map_err(|_| {
+                                    format!(
+                                        "invalid value for {} in environment variable {}: {:?}",
+                                        stringify!(#name),
+                                        #var,
+                                        value
+                                    )
+                                })?,
+                                Err(_) => #missing,
+                            }
+                        }
+                    }
+                    None => opts.default_value(),
+                };
+                quote! { let #name: #ty = #value; }
+            })
+            .collect();
+        let env_impl = quote! {
+            impl #impl_generics #struct_name #ty_generics #where_clause {
+                pub fn from_env() -> Result<Self, String> {
+                    #(#values)*
+                    #(#computed)*
+                    Ok(Self {
+                        #(#members: #field_names,)*
+                    })
+                }
+            }
+        };
+        implementations.extend(env_impl);
+    }
+
+    // field checks for `validate`/`try_new`
+    let mut validations = Vec::new();
+    for ((member, name), opts) in members.iter().zip(&field_names).zip(&field_options) {
+        if let Some(range) = &opts.validate_range {
+            let range_text = range_text(range);
+            validations.push(quote! {
+                if !(#range).contains(&self.#member) {
+                    return Err(format!(
+                        "{} must be in range {}, got {:?}",
+                        stringify!(#name),
+                        #range_text,
+                        self.#member
+                    ));
+                }
+            });
+        }
+        if opts.non_empty {
+            validations.push(quote! {
+                if self.#member.is_empty() {
+                    return Err(format!("{} must not be empty", stringify!(#name)));
+                }
+            });
+        }
+        if let Some(check) = &opts.validate {
+            validations.push(quote! {
+                (#check)(&self.#member)
+                    .map_err(|err| format!("{}: {}", stringify!(#name), err))?;
+            });
+        }
+    }
+
+    if !validations.is_empty() {
+        let try_new = if options.init {
+            quote! {
+                pub fn try_new(#(#param_names: #param_types),*) -> Result<Self, String> {
+                    let value = Self::new(#(#param_names),*);
+                    value.validate()?;
+                    Ok(value)
+                }
+            }
+        } else {
+            TokenStream2::new()
+        };
+        let validate_impl = quote! {
+            impl #impl_generics #struct_name #ty_generics #where_clause {
+                pub fn validate(&self) -> Result<(), String> {
+                    #(#validations)*
+                    Ok(())
+                }
+
+                #try_new
+            }
+        };
+        implementations.extend(validate_impl);
+    }
+
+    // Debug (repr option)
+    if options.repr {
+        // `#[field(sensitive)]` values print as "***"; with runtime_redact the
+        // masking follows a per-type flag toggled by `set_redact`
+        let redact_check = if options.runtime_redact {
+            quote! { REDACT.load(std::sync::atomic::Ordering::Relaxed) }
+        } else {
+            quote! { true }
+        };
+        let shown: Vec<usize> = (0..fields.len())
+            .filter(|&i| field_options[i].repr.unwrap_or(true))
+            .collect();
+        let debug_values: Vec<_> = shown
+            .iter()
+            .map(|&i| (&members[i], &field_options[i]))
+            .map(|(member, opts)| {
+                if opts.sensitive {
+                    quote! {
+                        if #redact_check {
+                            &"***" as &dyn std::fmt::Debug
+                        } else {
+                            &self.#member
+                        }
+                    }
+                } else if let Some(limit) = opts.debug_truncate {
+                    let limit = limit as usize;
+                    quote! { &__Truncated(&self.#member, #limit) }
+                } else {
+                    quote! { &self.#member }
+                }
+            })
+            .collect();
+
+        // `#[field(debug_truncate = N)]` lists only the first N elements
+        if shown
+            .iter()
+            .any(|&i| field_options[i].debug_truncate.is_some())
+        {
+            implementations.extend(quote! {
+                struct __Truncated<'__a, T>(&'__a T, usize);
+
+                impl<'__a, T> std::fmt::Debug for __Truncated<'__a, T>
+                where
+                    &'__a T: IntoIterator,
+                    <&'__a T as IntoIterator>::Item: std::fmt::Debug,
+                {
+                    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                        let mut list = f.debug_list();
+                        let mut items = self.0.into_iter();
+                        list.entries(items.by_ref().take(self.1));
+                        if items.next().is_some() {
+                            list.entry(&format_args!("..."));
+                        }
+                        list.finish()
+                    }
+                }
+            });
+        }
+
+        // keys match the serialized names with debug_use_serde_names
+        let debug_names: Vec<String> = shown
+            .iter()
+            .map(|&i| (&members[i], &fields[i]))
+            .map(|(member, field)| {
+                options
+                    .debug_use_serde_names
+                    .then(|| serde_rename(&field.attrs))
+                    .flatten()
+                    .unwrap_or_else(|| quote!(#member).to_string())
+            })
+            .collect();
+
+        // debug_address tells aliased instances apart: `Point@0x7ffd... { .. }`
+        let label = if options.debug_address {
+            quote! { &format!("{}@{:p}", stringify!(#struct_name), self) }
+        } else {
+            quote! { stringify!(#struct_name) }
+        };
+        let debug_body = if options.repr_style == ReprStyle::Json {
+            // `{"name": "Alice", "age": 30}` with each value's Debug output;
+            // tuple structs have no keys and print an array, `["Alice", 30]`
+            let mut entries: Vec<_> = debug_names
+                .iter()
+                .map(|name| {
+                    if is_tuple {
+                        "{:?}".to_string()
+                    } else {
+                        format!("\"{}\": {{:?}}", name)
+                    }
+                })
+                .collect();
+            let mut debug_values = debug_values.clone();
+            if options.debug_address {
+                entries.push(if is_tuple {
+                    "\"@{:p}\"".to_string()
+                } else {
+                    "\"@address\": \"{:p}\"".to_string()
+                });
+                debug_values.push(quote! { self });
+            }
+            let format = if is_tuple {
+                format!("[{}]", entries.join(", "))
+            } else {
+                format!("{{{{{}}}}}", entries.join(", "))
+            };
+            quote! {
+                write!(f, #format, #(#debug_values),*)
+            }
+        } else if is_tuple {
+            quote! {
+                f.debug_tuple(#label)
+                    #(.field(#debug_values))*
+                    .finish()
+            }
+        } else {
+            quote! {
+                f.debug_struct(#label)
+                    #(.field(#debug_names, #debug_values))*
+                    .finish()
+            }
+        };
+        let generics = with_bounds(
+            &input.generics,
+            quote!(std::fmt::Debug),
+            &marker_params,
+            &projected,
+        );
+        let (impl_generics, _, where_clause) = generics.split_for_impl();
+        let debug_impl = quote! {
+            impl #impl_generics std::fmt::Debug for #struct_name #ty_generics #where_clause {
+                fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                    #debug_body
+                }
+            }
+        };
+        if options.repr_style != ReprStyle::Custom {
+            implementations.extend(debug_impl);
+        }
+
+        // `debug_with(|name| ...)` keeps only the fields the predicate accepts
+        // (debug_with option)
+        if options.debug_with {
+            let pushes = debug_names.iter().zip(&debug_values).map(|(name, value)| {
+                let entry = if is_tuple {
+                    quote! { format!("{:?}", #value) }
+                } else {
+                    quote! { format!("{}: {:?}", #name, #value) }
+                };
+                quote! {
+                    if include(#name) {
+                        entries.push(#entry);
+                    }
+                }
+            });
+            let layout = if is_tuple {
+                quote! { format!("{}({})", stringify!(#struct_name), entries.join(", ")) }
+            } else {
+                quote! { format!("{} {{ {} }}", stringify!(#struct_name), entries.join(", ")) }
+            };
+            implementations.extend(quote! {
+                impl #impl_generics #struct_name #ty_generics #where_clause {
+                    pub fn debug_with(&self, include: impl Fn(&str) -> bool) -> String {
+                        let mut entries: Vec<String> = Vec::new();
+                        #(#pushes)*
+                        if entries.is_empty() {
+                            return stringify!(#struct_name).to_string();
+                        }
+                        #layout
+                    }
+                }
+            });
+        }
+
+        if options.runtime_redact {
+            let redact_impl = quote! {
+                static REDACT: std::sync::atomic::AtomicBool =
+                    std::sync::atomic::AtomicBool::new(true);
+
+                impl #impl_generics #struct_name #ty_generics #where_clause {
+                    pub fn set_redact(enabled: bool) {
+                        REDACT.store(enabled, std::sync::atomic::Ordering::Relaxed);
+                    }
+
+                    pub fn is_redacted() -> bool {
+                        REDACT.load(std::sync::atomic::Ordering::Relaxed)
+                    }
+                }
+            };
+            implementations.extend(redact_impl);
+        }
+    }
+
+    // fields that take part in eq/order and in hash
+    let compared: Vec<usize> = (0..fields.len())
+        .filter(|&i| field_options[i].compared())
+        .collect();
+    // hash_subset hashes only the `#[field(id)]` fields, and
+    // `#[field(hash_key)]` fields form a composite key; that stays sound as
+    // long as they are all compared, since equal values then hash equal
+    let keys: Vec<usize> = (0..fields.len())
+        .filter(|&i| field_options[i].hash_key || (options.hash_subset && field_options[i].id))
+        .collect();
+    let hashed: Vec<usize> = if options.hash_subset || !keys.is_empty() {
+        if keys.is_empty() {
+            panic!("hash_subset requires at least one #[field(id)] field");
+        }
+        if keys.iter().any(|&i| !field_options[i].compared()) {
+            panic!("#[field(id)] and #[field(hash_key)] fields must take part in eq");
+        }
+        keys
+    } else if options.derive_eq_from_ord && field_options.iter().any(|opts| opts.sort_key) {
+        // equality then only sees the sort key, so the hash must too
+        (0..fields.len())
+            .filter(|&i| field_options[i].sort_key)
+            .collect()
+    } else {
+        (0..fields.len())
+            .filter(|&i| field_options[i].hashed())
+            .collect()
+    };
+
+    // `#[field(by_ptr)]` compares and hashes `Arc`/`Rc` fields by address
+    let pointer_paths: Vec<_> = field_types
+        .iter()
+        .zip(&field_options)
+        .map(|(ty, opts)| {
+            if !opts.by_ptr {
+                return None;
+            }
+            match type_ident(ty).map(|ident| ident.to_string()).as_deref() {
+                Some("Arc") => Some(quote!(std::sync::Arc)),
+                Some("Rc") => Some(quote!(std::rc::Rc)),
+                _ => panic!("by_ptr requires an Arc or Rc field"),
+            }
+        })
+        .collect();
+    let eq_expr = |i: usize| {
+        let member = &members[i];
+        if let Some(compare_with) = &field_options[i].compare_with {
+            return quote! { #compare_with(&self.#member, &other.#member) };
+        }
+        if let Some(tolerance) = &field_options[i].approx_eq {
+            let ty = field_types[i];
+            return match type_ident(ty).map(|ident| ident.to_string()).as_deref() {
+                Some("f32") | Some("f64") => {
+                    quote! { (self.#member - other.#member).abs() <= #tolerance }
+                }
+                _ => quote! {
+                    self.#member.len() == other.#member.len()
+                        && self.#member.iter().zip(other.#member.iter()).all(|(__lhs, __rhs)| (__lhs - __rhs).abs() <= #tolerance)
+                },
+            };
+        }
+        match &pointer_paths[i] {
+            Some(path) => quote! { #path::ptr_eq(&self.#member, &other.#member) },
+            None if field_options[i].deref_eq => quote! { *self.#member == *other.#member },
+            None => quote! { self.#member == other.#member },
+        }
+    };
+    // `#[field(reverse)]` swaps the operands for descending order;
+    // `#[field(none_last)]` sorts `None` after every `Some` either way
+    let cmp_expr = |i: usize| {
+        let member = &members[i];
+        let (lhs, rhs) = if field_options[i].reverse {
+            (quote!(other), quote!(self))
+        } else {
+            (quote!(self), quote!(other))
+        };
+        if field_options[i].none_last {
+            let (a, b) = if field_options[i].reverse {
+                (quote!(__rhs), quote!(__lhs))
+            } else {
+                (quote!(__lhs), quote!(__rhs))
+            };
+            return quote! {
+                match (&self.#member, &other.#member) {
+                    (None, None) => std::cmp::Ordering::Equal,
+                    (None, Some(_)) => std::cmp::Ordering::Greater,
+                    (Some(_), None) => std::cmp::Ordering::Less,
+                    (Some(__lhs), Some(__rhs)) => #a.cmp(#b),
+                }
+            };
+        }
+        match &pointer_paths[i] {
+            Some(path) => quote! {
+                (#path::as_ptr(&#lhs.#member) as *const ()).cmp(&(#path::as_ptr(&#rhs.#member) as *const ()))
+            },
+            None if field_options[i].deref_eq => {
+                quote! { Ord::cmp(&*#lhs.#member, &*#rhs.#member) }
+            }
+            None => quote! { Ord::cmp(&#lhs.#member, &#rhs.#member) },
+        }
+    };
+    // `f32`/`f64` fields hash their bits, with `-0.0` folded into `0.0` so
+    // values that compare equal hash equal
+    let is_float: Vec<bool> = field_types
+        .iter()
+        .map(|ty| matches!(type_ident(ty), Some(ident) if ident == "f32" || ident == "f64"))
+        .collect();
+    let float_bits = |value: TokenStream2| {
+        quote! { (if #value == 0.0 { 0.0 } else { #value }).to_bits() }
+    };
+    let hash_stmt = |i: usize| {
+        let member = &members[i];
+        match &pointer_paths[i] {
+            Some(path) => quote! { std::ptr::hash(#path::as_ptr(&self.#member), state); },
+            None if is_float[i] => {
+                let bits = float_bits(quote!(self.#member));
+                quote! { std::hash::Hash::hash(&#bits, state); }
+            }
+            None if field_options[i].deref_eq => {
+                quote! { std::hash::Hash::hash(&*self.#member, state); }
+            }
+            None => quote! { std::hash::Hash::hash(&self.#member, state); },
+        }
+    };
+
+    // (eq_hash_consistency option)
+    if options.strict_eq_hash {
+        for (i, field) in fields.iter().enumerate() {
+            let is_compared = compared.contains(&i);
+            if is_compared != hashed.contains(&i) {
+                let message = if is_compared {
+                    "field is compared but excluded from hash (eq_hash_consistency = \"strict\")"
+                } else {
+                    "field is hashed but excluded from eq (eq_hash_consistency = \"strict\")"
+                };
+                implementations.extend(syn::Error::new_spanned(field, message).to_compile_error());
+            }
+        }
+    }
+
+    // Clone; `#[field(clone = deep)]` needs a hand-written impl that puts a
+    // copy of the pointee into a fresh `Rc`/`Arc`, and a bare `Mutex`/`RwLock`
+    // `#[field(lock)]` field is not `Clone` at all
+    let mut derives = Vec::new();
+    let bare_lock = (0..fields.len()).any(|i| {
+        field_options[i].lock
+            && !matches!(type_ident(field_types[i]), Some(ident) if ident == "Arc" || ident == "Rc")
+    });
+    if bare_lock {
+        // left to the user, e.g. by locking in a hand-written impl
+    } else if field_options.iter().any(|opts| opts.deep_clone) {
+        let values = (0..fields.len()).map(|i| {
+            let member = &members[i];
+            if !field_options[i].deep_clone {
+                return quote! { Clone::clone(&self.#member) };
+            }
+            match type_ident(field_types[i])
+                .map(|ident| ident.to_string())
+                .as_deref()
+            {
+                Some("Arc") => quote! { std::sync::Arc::new((*self.#member).clone()) },
+                Some("Rc") => quote! { std::rc::Rc::new((*self.#member).clone()) },
+                _ => panic!("#[field(clone = deep)] requires an Arc or Rc field"),
+            }
+        });
+        let generics = with_bounds(&input.generics, quote!(Clone), &marker_params, &projected);
+        let (impl_generics, _, where_clause) = generics.split_for_impl();
+        implementations.extend(quote! {
+            impl #impl_generics Clone for #struct_name #ty_generics #where_clause {
+                fn clone(&self) -> Self {
+                    Self {
+                        #(#members: #values,)*
+                    }
+                }
+            }
+        });
+    } else {
+        derives.push(quote!(Clone));
+    }
+
+    // PartialEq and Eq; without per-field customization the std derives do
+    // the same job (eq option); derive_eq_from_ord defines equality as
+    // `cmp == Equal` so the two can never disagree
+    if options.derive_eq_from_ord && !(options.eq && options.order) {
+        panic!("derive_eq_from_ord requires eq = true and order = true");
+    }
+    let derive_eq = options.eq
+        && !options.derive_eq_from_ord
+        && compared.len() == fields.len()
+        && (0..fields.len()).all(|i| {
+            field_options[i].compare_with.is_none()
+                && field_options[i].approx_eq.is_none()
+                && pointer_paths[i].is_none()
+                && !field_options[i].deref_eq
+        });
+    if options.eq {
+        if derive_eq {
+            derives.push(quote!(PartialEq));
+        } else {
+            let eq_body = if options.derive_eq_from_ord {
+                quote! { std::cmp::Ord::cmp(self, other) == std::cmp::Ordering::Equal }
+            } else if compared.is_empty() {
+                quote! { true }
+            } else {
+                let exprs = compared.iter().map(|&i| eq_expr(i));
+                quote! { #(#exprs)&&* }
+            };
+            let bound = if options.derive_eq_from_ord {
+                quote!(Ord)
+            } else {
+                quote!(PartialEq)
+            };
+            let generics = with_bounds(&input.generics, bound, &marker_params, &projected);
+            let (impl_generics, _, where_clause) = generics.split_for_impl();
+            let eq_impl = quote! {
+                impl #impl_generics PartialEq for #struct_name #ty_generics #where_clause {
+                    fn eq(&self, other: &Self) -> bool {
+                        #eq_body
+                    }
+                }
+            };
+            implementations.extend(eq_impl);
+        }
+
+        // `Eq` is unsound for fields like floats, where `NaN != NaN`; the
+        // derive would reject them, so force_eq always writes the impl
+        let has_float = compared.iter().any(|&i| {
+            let ty = field_types[i];
+            contains_float(quote!(#ty))
+        });
+        if !options.partial_eq_only && (options.force_eq || !has_float) {
+            if derive_eq && !has_float {
+                derives.push(quote!(Eq));
+            } else {
+                let eq_generics =
+                    with_bounds(&input.generics, quote!(Eq), &marker_params, &projected);
+                let (eq_impl_generics, _, eq_where_clause) = eq_generics.split_for_impl();
+                implementations.extend(quote! {
+                    impl #eq_impl_generics Eq for #struct_name #ty_generics #eq_where_clause {}
+                });
+            }
+        }
+    }
+
+    // `diff()` lists the fields that differ with their old and new Debug
+    // output; hidden and sensitive fields show "***" (diff option)
+    if options.diff {
+        let entries = (0..tracked).filter(|&i| !is_marker[i]).map(|i| {
+            let member = &members[i];
+            let masked = !field_options[i].repr.unwrap_or(true) || field_options[i].sensitive;
+            let (old, new) = if masked {
+                (quote!("***".to_string()), quote!("***".to_string()))
+            } else {
+                (
+                    quote!(format!("{:?}", self.#member)),
+                    quote!(format!("{:?}", other.#member)),
+                )
+            };
+            quote! {
+                if self.#member != other.#member {
+                    changes.push((stringify!(#member), #old, #new));
+                }
+            }
+        });
+        let generics = with_bounds(
+            &input.generics,
+            quote!(PartialEq + std::fmt::Debug),
+            &marker_params,
+            &projected,
+        );
+        let (impl_generics, _, where_clause) = generics.split_for_impl();
+        implementations.extend(quote! {
+            impl #impl_generics #struct_name #ty_generics #where_clause {
+                pub fn diff(&self, other: &Self) -> Vec<(&'static str, String, String)> {
+                    let mut changes = Vec::new();
+                    #(#entries)*
+                    changes
+                }
+            }
+        });
+    }
+
+    // `matches()` compares only the `#[field(key)]` fields, or what eq
+    // compares when none are marked (matches option)
+    if options.matches {
+        let keys: Vec<usize> = (0..fields.len())
+            .filter(|&i| field_options[i].key)
+            .collect();
+        let keys = if keys.is_empty() {
+            compared.clone()
+        } else {
+            keys
+        };
+        let body = if keys.is_empty() {
+            quote! { true }
+        } else {
+            let exprs = keys.iter().map(|&i| eq_expr(i));
+            quote! { #(#exprs)&&* }
+        };
+        let generics = with_bounds(
+            &input.generics,
+            quote!(PartialEq),
+            &marker_params,
+            &projected,
+        );
+        let (impl_generics, _, where_clause) = generics.split_for_impl();
+        implementations.extend(quote! {
+            impl #impl_generics #struct_name #ty_generics #where_clause {
+                pub fn matches(&self, other: &Self) -> bool {
+                    #body
+                }
+            }
+        });
+    }
+
+    // (order option)
+    if options.order {
+        // a `#[field(sort_key)]` field replaces the field-by-field ordering
+        let sort_keys: Vec<usize> = (0..fields.len())
+            .filter(|&i| field_options[i].sort_key)
+            .collect();
+        if sort_keys.len() > 1 {
+            panic!("Only one field can be marked #[field(sort_key)]");
+        }
+        let mut order_fields = if sort_keys.is_empty() {
+            compared.clone()
+        } else {
+            sort_keys
+        };
+        // `#[field(tiebreak)]` fields only decide between values that are
+        // otherwise equal, giving a total order that eq does not see, so such
+        // values are `==` yet not `Ordering::Equal`
+        order_fields.extend((0..fields.len()).filter(|&i| field_options[i].tiebreak));
+
+        // the std derives order field by field too, with their own bounds
+        let derive_ord = order_fields.len() == fields.len()
+            && (0..fields.len()).all(|i| {
+                !field_options[i].reverse
+                    && !field_options[i].none_last
+                    && pointer_paths[i].is_none()
+                    && !field_options[i].deref_eq
+                    && !field_options[i].tiebreak
+                    && field_options[i].compare_with.is_none()
+                    && field_options[i].approx_eq.is_none()
+            });
+        // fields with custom equality order as Equal whenever eq says so,
+        // keeping `cmp == Equal` in line with `==`
+        let order_exprs: Vec<_> = order_fields
+            .iter()
+            .map(|&i| {
+                let ordering = cmp_expr(i);
+                if field_options[i].compare_with.is_some() || field_options[i].approx_eq.is_some() {
+                    let eq = eq_expr(i);
+                    quote! { if #eq { std::cmp::Ordering::Equal } else { #ordering } }
+                } else {
+                    ordering
+                }
+            })
+            .collect();
+
+        let generics = with_bounds(&input.generics, quote!(Ord), &marker_params, &projected);
+        let (impl_generics, _, where_clause) = generics.split_for_impl();
+        let ord_impl = if derive_ord {
+            derives.push(quote!(PartialOrd));
+            derives.push(quote!(Ord));
+            TokenStream2::new()
+        } else {
+            quote! {
+                impl #impl_generics PartialOrd for #struct_name #ty_generics #where_clause {
+                    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+                        Some(self.cmp(other))
+                    }
+                }
+
+                impl #impl_generics Ord for #struct_name #ty_generics #where_clause {
+                    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+                        #(
+                            match #order_exprs {
+                                std::cmp::Ordering::Equal => {}
+                                ordering => return ordering,
+                            }
+                        )*
+                        std::cmp::Ordering::Equal
+                    }
+                }
+            }
+        };
+        implementations.extend(ord_impl);
+    }
+
+    // Hash (unsafe_hash option)
+    // values that are equal under `compare_with`/`approx_eq` can still differ
+    // field by field, so no hash of those fields agrees with eq
+    if options.unsafe_hash
+        && hashed.iter().any(|&i| {
+            field_options[i].compare_with.is_some() || field_options[i].approx_eq.is_some()
+        })
+    {
+        panic!("compare_with and approx_eq fields must not be hashed; add #[field(hash = false)]");
+    }
+    // without per-field customization the std derive does the same job,
+    // provided eq is derived as well
+    let derive_hash = options.unsafe_hash
+        && derive_eq
+        && !options.hash_type_tag
+        && !options.stable_hash
+        && !options.cache_hash
+        && hashed.len() == fields.len()
+        && pointer_paths.iter().all(Option::is_none)
+        && !hashed.iter().any(|&i| field_options[i].deref_eq)
+        && !hashed.iter().any(|&i| is_float[i]);
+    if derive_hash {
+        derives.push(quote!(Hash));
+    }
+
+    if options.cache_hash {
+        let params = (0..tracked).map(|i| {
+            let (name, ty) = (&field_names[i], field_types[i]);
+            if hashed.contains(&i) {
+                quote! { #name: &#ty }
+            } else {
+                quote! { _: &#ty }
+            }
+        });
+        let mut hash_stmts: Vec<_> = hashed
+            .iter()
+            .map(|&i| {
+                let name = &field_names[i];
+                match &pointer_paths[i] {
+                    Some(path) => quote! { std::ptr::hash(#path::as_ptr(#name), &mut hasher); },
+                    None if is_float[i] => {
+                        let bits = float_bits(quote!((*#name)));
+                        quote! { std::hash::Hash::hash(&#bits, &mut hasher); }
+                    }
+                    None if field_options[i].deref_eq => {
+                        quote! { std::hash::Hash::hash(&**#name, &mut hasher); }
+                    }
+                    None => quote! { std::hash::Hash::hash(#name, &mut hasher); },
+                }
+            })
+            .collect();
+        if options.hash_type_tag {
+            hash_stmts.insert(
+                0,
+                quote! { std::hash::Hash::hash(std::any::type_name::<Self>(), &mut hasher); },
+            );
+        }
+        if options.stable_hash {
+            let count = hashed.len() as u64;
+            hash_stmts.insert(
+                0,
+                quote! { std::hash::Hasher::write_u64(&mut hasher, #count); },
+            );
+        }
+        let user_members = &members[..tracked];
+        let generics = with_bounds(
+            &input.generics,
+            quote!(std::hash::Hash),
+            &marker_params,
+            &projected,
+        );
+        let (impl_generics, _, where_clause) = generics.split_for_impl();
+        implementations.extend(quote! {
+            impl #impl_generics #struct_name #ty_generics #where_clause {
+                fn __cache_hash(#(#params),*) -> u64 {
+                    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+                    #(#hash_stmts)*
+                    std::hash::Hasher::finish(&hasher)
+                }
+
+                pub fn recompute_hash(&self) -> u64 {
+                    Self::__cache_hash(#(&self.#user_members),*)
+                }
+            }
+
+            impl #impl_generics std::hash::Hash for #struct_name #ty_generics #where_clause {
+                fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+                    if self.cached_hash == 0 {
+                        state.write_u64(self.recompute_hash());
+                    } else {
+                        state.write_u64(self.cached_hash);
+                    }
+                }
+            }
+        });
+    } else if options.unsafe_hash && !derive_hash {
+        let mut hash_stmts: Vec<_> = hashed.iter().map(|&i| hash_stmt(i)).collect();
+        // the type name keeps equal field values of different types apart
+        if options.hash_type_tag {
+            hash_stmts.insert(0, quote! { std::any::type_name::<Self>().hash(state); });
+        }
+        // stable_hash leads with the field count as a fixed-width `u64`, then
+        // the fields in declaration order, so a nested value cannot pass for
+        // a prefix of its parent
+        if options.stable_hash {
+            let count = hashed.len() as u64;
+            hash_stmts.insert(0, quote! { state.write_u64(#count); });
+        }
+        let generics = with_bounds(
+            &input.generics,
+            quote!(std::hash::Hash),
+            &marker_params,
+            &projected,
+        );
+        let (impl_generics, _, where_clause) = generics.split_for_impl();
+        let hash_impl = quote! {
+            impl #impl_generics std::hash::Hash for #struct_name #ty_generics #where_clause {
+                fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+                    #(#hash_stmts)*
+                }
+            }
+        };
+        implementations.extend(hash_impl);
+    }
+
+    // field metadata; proc-macro crates cannot export types, so each entry is
+    // a `(name, type_name)` pair instead of a shared `FieldInfo` struct
+    let (info_members, info_types) = (&members[..tracked], &field_types[..tracked]);
+    let field_info_impl = quote! {
+        impl #impl_generics #struct_name #ty_generics #where_clause {
+            pub fn field_info() -> Vec<(&'static str, &'static str)> {
+                vec![#((stringify!(#info_members), std::any::type_name::<#info_types>())),*]
+            }
+        }
+    };
+    implementations.extend(field_info_impl);
+
+    // positional reflection; `get_field` goes through Debug, so it needs repr
+    let field_count = tracked;
+    let positions = 0..tracked;
+    let position_values = (0..tracked).map(|i| {
+        let member = &members[i];
+        if field_options[i].sensitive {
+            quote! { &"***" }
+        } else {
+            quote! { &self.#member }
+        }
+    });
+    implementations.extend(quote! {
+        impl #impl_generics #struct_name #ty_generics #where_clause {
+            pub const FIELD_COUNT: usize = #field_count;
+        }
+    });
+    if options.repr {
+        let generics = with_bounds(
+            &input.generics,
+            quote!(std::fmt::Debug),
+            &marker_params,
+            &projected,
+        );
+        let (impl_generics, _, where_clause) = generics.split_for_impl();
+        implementations.extend(quote! {
+            impl #impl_generics #struct_name #ty_generics #where_clause {
+                pub fn get_field(&self, i: usize) -> Option<&dyn std::fmt::Debug> {
+                    match i {
+                        #(#positions => Some(#position_values),)*
+                        _ => None,
+                    }
+                }
+            }
+        });
+    }
+
+    // `value["name"]` for dict-style access, panicking on an unknown name;
+    // the output is the field type when every field shares it, otherwise
+    // `dyn Debug` like `get_field` (index_by_name option)
+    if options.index_by_name {
+        if is_tuple {
+            panic!("index_by_name requires a struct with named fields");
+        }
+        let first = field_types.first().map(|ty| quote!(#ty).to_string());
+        let homogeneous = (0..tracked).all(|i| {
+            let ty = field_types[i];
+            !field_options[i].sensitive && Some(quote!(#ty).to_string()) == first
+        });
+        let names = &field_names[..tracked];
+        let unknown = format!("{} has no field {{:?}}", struct_name);
+        let (generics, output, values) = if homogeneous && tracked > 0 {
+            let ty = field_types[0];
+            let values: Vec<_> = members[..tracked]
+                .iter()
+                .map(|m| quote!(&self.#m))
+                .collect();
+            (input.generics.clone(), quote!(#ty), values)
+        } else {
+            let generics = with_bounds(
+                &input.generics,
+                quote!(std::fmt::Debug + 'static),
+                &marker_params,
+                &projected,
+            );
+            let values: Vec<_> = (0..tracked)
+                .map(|i| {
+                    let member = &members[i];
+                    if field_options[i].sensitive {
+                        quote! { &"***" }
+                    } else {
+                        quote! { &self.#member }
+                    }
+                })
+                .collect();
+            (generics, quote!(dyn std::fmt::Debug), values)
+        };
+        let (impl_generics, _, where_clause) = generics.split_for_impl();
+        implementations.extend(quote! {
+            impl #impl_generics std::ops::Index<&str> for #struct_name #ty_generics #where_clause {
+                type Output = #output;
+
+                fn index(&self, name: &str) -> &Self::Output {
+                    match name {
+                        #(stringify!(#names) => #values,)*
+                        other => panic!(#unknown, other),
+                    }
+                }
+            }
+        });
+    }
+
+    // `try_get_i32(name)` and friends, one per primitive field type, for
+    // readers that pick fields by name at runtime; `sensitive` fields are
+    // left out (typed_get option)
+    if options.typed_get {
+        if is_tuple {
+            panic!("typed_get requires a struct with named fields");
+        }
+        const PRIMITIVES: &[&str] = &[
+            "bool", "char", "i8", "i16", "i32", "i64", "i128", "isize", "u8", "u16", "u32", "u64",
+            "u128", "usize", "f32", "f64",
+        ];
+        let getters = PRIMITIVES.iter().filter_map(|&primitive| {
+            let matching: Vec<usize> = (0..tracked)
+                .filter(|&i| {
+                    let ty = field_types[i];
+                    !field_options[i].sensitive && quote!(#ty).to_string() == primitive
+                })
+                .collect();
+            if matching.is_empty() {
+                return None;
+            }
+            let fn_name = format_ident!("try_get_{}", primitive);
+            let ty = format_ident!("{}", primitive);
+            let names = matching.iter().map(|&i| &field_names[i]);
+            let values = matching.iter().map(|&i| &members[i]);
+            Some(quote! {
+                pub fn #fn_name(&self, name: &str) -> Option<#ty> {
+                    match name {
+                        #(stringify!(#names) => Some(self.#values),)*
+                        _ => None,
+                    }
+                }
+            })
+        });
+        implementations.extend(quote! {
+            impl #impl_generics #struct_name #ty_generics #where_clause {
+                #(#getters)*
+            }
+        });
+    }
+
+    // minimal JSON Schema from the field types (schema option); `Option`
+    // fields are nullable and not required
+    if options.schema {
+        if is_tuple {
+            panic!("schema requires a struct with named fields");
+        }
+        let mut properties = Vec::new();
+        let mut required = Vec::new();
+        for i in (0..tracked).filter(|&i| !is_marker[i]) {
+            let name = serde_rename(&fields[i].attrs).unwrap_or_else(|| field_names[i].to_string());
+            properties.push(format!("\"{}\":{}", name, schema_type(field_types[i])));
+            let optional = matches!(type_ident(field_types[i]), Some(ident) if ident == "Option");
+            if !optional {
+                required.push(format!("\"{}\"", name));
+            }
+        }
+        let schema = format!(
+            "{{\"title\":\"{}\",\"type\":\"object\",\"properties\":{{{}}},\"required\":[{}]}}",
+            struct_name,
+            properties.join(","),
+            required.join(",")
+        );
+        implementations.extend(quote! {
+            impl #impl_generics #struct_name #ty_generics #where_clause {
+                pub fn json_schema() -> &'static str {
+                    #schema
+                }
+            }
+        });
+    }
+
+    // stable field numbers from `#[field(tag = N)]`, for wire-adjacent tooling
+    let tagged: Vec<(u32, usize)> = (0..fields.len())
+        .filter_map(|i| field_options[i].tag.map(|tag| (tag, i)))
+        .collect();
+    if !tagged.is_empty() {
+        for (n, (tag, _)) in tagged.iter().enumerate() {
+            if tagged[..n].iter().any(|(other, _)| other == tag) {
+                panic!("Duplicate #[field(tag = {})]", tag);
+            }
+        }
+        let tags = tagged.iter().map(|(tag, _)| tag);
+        let tagged_members = tagged.iter().map(|&(_, i)| &members[i]);
+        let tagged_impl = quote! {
+            impl #impl_generics #struct_name #ty_generics #where_clause {
+                pub fn tagged_fields(&self) -> Vec<(u32, String)> {
+                    vec![#((#tags, format!("{:?}", self.#tagged_members))),*]
+                }
+            }
+        };
+        implementations.extend(tagged_impl);
+    }
+
+    // read-only getters for frozen fields
+    if options.frozen {
+        let inline = if options.inline_getters {
+            quote! { #[inline] }
+        } else {
+            TokenStream2::new()
+        };
+        // `#[field(lock)]` fields get guard accessors under the same name instead
+        let getters = (0..tracked).filter(|&i| !field_options[i].lock).map(|i| {
+            let doc = match &field_options[i].doc {
+                Some(doc) => quote! { #[doc = #doc] },
+                None => TokenStream2::new(),
+            };
+            let (name, ty, member) = (&field_names[i], field_types[i], &members[i]);
+            // maps also get an iterator so callers need not borrow the map itself
+            let iter = match type_ident(ty).map(|ident| ident.to_string()).as_deref() {
+                Some("HashMap") | Some("BTreeMap") => {
+                    let name_iter = format_ident!("{}_iter", name);
+                    quote! {
+                        #inline
+                        pub fn #name_iter(&self) -> <&'_ #ty as IntoIterator>::IntoIter {
+                            self.#member.iter()
+                        }
+                    }
+                }
+                _ => TokenStream2::new(),
+            };
+            quote! {
+                #doc
+                #inline
+                pub fn #name(&self) -> &#ty {
+                    &self.#member
+                }
+
+                #iter
+            }
+        });
+        let getters_impl = quote! {
+            impl #impl_generics #struct_name #ty_generics #where_clause {
+                #(#getters)*
+            }
+        };
+        implementations.extend(getters_impl);
+    }
+
+    // guard accessors for `#[field(lock)]` `Mutex`/`RwLock` fields, also
+    // behind an `Arc`/`Rc`
+    for i in (0..fields.len()).filter(|&i| field_options[i].lock) {
+        let member = &members[i];
+        let name = &field_names[i];
+        let mut lock_ty = field_types[i];
+        if let Some("Arc") | Some("Rc") = type_ident(lock_ty)
+            .map(|ident| ident.to_string())
+            .as_deref()
+        {
+            lock_ty = type_argument(lock_ty).expect("Arc/Rc field without a type argument");
+        }
+        let inner = type_argument(lock_ty);
+        let accessors = match (
+            type_ident(lock_ty)
+                .map(|ident| ident.to_string())
+                .as_deref(),
+            inner,
+        ) {
+            (Some("Mutex"), Some(inner)) => quote! {
+                pub fn #name(&self) -> std::sync::LockResult<std::sync::MutexGuard<'_, #inner>> {
+                    self.#member.lock()
+                }
+            },
+            (Some("RwLock"), Some(inner)) => {
+                let name_mut = format_ident!("{}_mut", name);
+                quote! {
+                    pub fn #name(&self) -> std::sync::LockResult<std::sync::RwLockReadGuard<'_, #inner>> {
+                        self.#member.read()
+                    }
+
+                    pub fn #name_mut(&self) -> std::sync::LockResult<std::sync::RwLockWriteGuard<'_, #inner>> {
+                        self.#member.write()
+                    }
+                }
+            }
+            _ => panic!("#[field(lock)] requires a Mutex or RwLock field"),
+        };
+        implementations.extend(quote! {
+            impl #impl_generics #struct_name #ty_generics #where_clause {
+                #accessors
+            }
+        });
+    }
+
+    // `#[field(getter_mut)]` keeps the field private behind `name()`/`name_mut()`
+    let getter_muts: Vec<_> = (0..fields.len())
+        .filter(|&i| field_options[i].getter_mut)
+        .map(|i| {
+            if options.frozen {
+                panic!("#[field(getter_mut)] cannot be used on frozen structs");
+            }
+            let (name, ty, member) = (&field_names[i], field_types[i], &members[i]);
+            let name_mut = format_ident!("{}_mut", name);
+            quote! {
+                pub fn #name(&self) -> &#ty {
+                    &self.#member
+                }
+
+                pub fn #name_mut(&mut self) -> &mut #ty {
+                    &mut self.#member
+                }
+            }
+        })
+        .collect();
+    if !getter_muts.is_empty() {
+        implementations.extend(quote! {
+            impl #impl_generics #struct_name #ty_generics #where_clause {
+                #(#getter_muts)*
+            }
+        });
+    }
+
+    // setters recording the names of changed fields (track_changes option)
+    if options.track_changes {
+        let setters = (0..tracked).map(|i| {
+            let (name, ty) = (&field_names[i], field_types[i]);
+            let setter = format_ident!("set_{}", name);
+            let record = record_change(name);
+            quote! {
+                pub fn #setter(&mut self, value: #ty) {
+                    self.#name = value;
+                    #record
+                }
+            }
+        });
+        implementations.extend(quote! {
+            impl #impl_generics #struct_name #ty_generics #where_clause {
+                #(#setters)*
+
+                pub fn take_changes(&mut self) -> Vec<&'static str> {
+                    std::mem::take(&mut self.changed)
+                }
+            }
+        });
+    }
+
+    // private fields behind `name()` getters and `set_name()` setters
+    // (properties option); frozen structs already have the getters, and
+    // track_changes brings its own setters
+    if options.properties && !options.frozen {
+        let accessors = (0..tracked)
+            .filter(|&i| !field_options[i].getter_mut)
+            .map(|i| {
+                let (name, ty, member) = (&field_names[i], field_types[i], &members[i]);
+                let setter = if options.track_changes {
+                    TokenStream2::new()
+                } else {
+                    let set_name = format_ident!("set_{}", name);
+                    quote! {
+                        pub fn #set_name(&mut self, value: #ty) {
+                            self.#member = value;
+                        }
+                    }
+                };
+                // `#[field(lock)]` fields already have guard accessors by
+                // that name
+                let getter = if field_options[i].lock {
+                    TokenStream2::new()
+                } else {
+                    quote! {
+                        pub fn #name(&self) -> &#ty {
+                            &self.#member
+                        }
+                    }
+                };
+                quote! {
+                    #getter
+
+                    #setter
+                }
+            });
+        implementations.extend(quote! {
+            impl #impl_generics #struct_name #ty_generics #where_clause {
+                #(#accessors)*
+            }
+        });
+    }
+
+    // by-value `with_*` methods (functional option)
+    if options.functional {
+        let withs = (0..tracked).map(|i| {
+            let (name, ty, member) = (&field_names[i], field_types[i], &members[i]);
+            let with_name = format_ident!("with_{}", name);
+            let record = record_change(name);
+            quote! {
+                pub fn #with_name(mut self, value: #ty) -> Self {
+                    self.#member = value;
+                    #record
+                    #refresh_hash
+                    self
+                }
+            }
+        });
+        let functional_impl = quote! {
+            impl #mutator_impl_generics #struct_name #ty_generics #mutator_where_clause {
+                #(#withs)*
+            }
+        };
+        implementations.extend(functional_impl);
+    }
+
+    // (merge option)
+    if options.merge {
+        let merged = (0..tracked)
+            .filter(|&i| !field_options[i].merge_skip)
+            .map(|i| {
+                let member = &members[i];
+                let record = record_change(&field_names[i]);
+                quote! {
+                    self.#member = other.#member;
+                    #record
+                }
+            });
+        let merge_impl = quote! {
+            impl #mutator_impl_generics #struct_name #ty_generics #mutator_where_clause {
+                pub fn merge(&mut self, other: Self) {
+                    #(#merged)*
+                    #refresh_hash
+                }
+            }
+        };
+        implementations.extend(merge_impl);
+    }
+
+    // positional `Index<usize>` (index option)
+    if options.index {
+        let element = field_types[0];
+        let same_type = field_types
+            .iter()
+            .all(|ty| quote!(#ty).to_string() == quote!(#element).to_string());
+        if !is_tuple || !same_type {
+            panic!("index requires a tuple struct whose fields all have the same type");
+        }
+        let len = fields.len();
+        let indices = 0..len;
+        let index_impl = quote! {
+            impl #impl_generics std::ops::Index<usize> for #struct_name #ty_generics #where_clause {
+                type Output = #element;
+
+                fn index(&self, index: usize) -> &Self::Output {
+                    match index {
+                        #(#indices => &self.#members,)*
+                        _ => panic!(
+                            "index out of bounds: the len is {} but the index is {}",
+                            #len,
+                            index
+                        ),
+                    }
+                }
+            }
+        };
+        implementations.extend(index_impl);
+    }
+
+    // (newtype option)
+    if options.newtype {
+        if !is_tuple || fields.len() != 1 {
+            panic!("newtype requires a tuple struct with exactly one field");
+        }
+        let inner = field_types[0];
+        let kind = type_ident(inner).map(|ident| ident.to_string());
+        let mut ref_generics = input.generics.clone();
+        ref_generics.params.insert(0, syn::parse_quote!('__a));
+        let (ref_impl_generics, _, _) = ref_generics.split_for_impl();
+
+        // `collect()`/`extend()` go through the inner collection's impls
+        let mut collect_generics = input.generics.clone();
+        let predicates = &mut collect_generics.make_where_clause().predicates;
+        predicates.push(syn::parse_quote!(#inner: FromIterator<<#inner as IntoIterator>::Item>));
+        predicates.push(syn::parse_quote!(#inner: Extend<<#inner as IntoIterator>::Item>));
+        let collect_where_clause = &collect_generics.where_clause;
+
+        // conversions both ways; a validated field only gets the unwrapping one,
+        // and a bare type parameter cannot be the target of a foreign `From`
+        if field_options[0].validate_range.is_none()
+            && !field_options[0].non_empty
+            && field_options[0].validate.is_none()
+        {
+            implementations.extend(quote! {
+                impl #impl_generics From<#inner> for #struct_name #ty_generics #where_clause {
+                    fn from(value: #inner) -> Self {
+                        Self(value)
+                    }
+                }
+            });
+        }
+        let is_param = input.generics.type_params().any(
+            |param| matches!(inner, Type::Path(type_path) if type_path.path.is_ident(&param.ident)),
+        );
+        if !is_param {
+            implementations.extend(quote! {
+                impl #impl_generics From<#struct_name #ty_generics> for #inner #where_clause {
+                    fn from(value: #struct_name #ty_generics) -> Self {
+                        value.0
+                    }
+                }
+            });
+        }
+
+        // borrowing access; frozen newtypes stay read-only
+        implementations.extend(quote! {
+            impl #impl_generics AsRef<#inner> for #struct_name #ty_generics #where_clause {
+                fn as_ref(&self) -> &#inner {
+                    &self.0
+                }
+            }
+        });
+        if !options.frozen {
+            implementations.extend(quote! {
+                impl #impl_generics AsMut<#inner> for #struct_name #ty_generics #where_clause {
+                    fn as_mut(&mut self) -> &mut #inner {
+                        &mut self.0
+                    }
+                }
+            });
+        }
+
+        // known collections get delegating accessors
+        let collection_impl = match kind.as_deref() {
+            Some("Vec") | Some("HashMap") | Some("HashSet") => quote! {
+                impl #impl_generics #struct_name #ty_generics #where_clause {
+                    pub fn len(&self) -> usize {
+                        self.0.len()
+                    }
+
+                    pub fn is_empty(&self) -> bool {
+                        self.0.is_empty()
+                    }
+
+                    pub fn iter(&self) -> <&'_ #inner as IntoIterator>::IntoIter {
+                        self.0.iter()
+                    }
+                }
+
+                impl #impl_generics IntoIterator for #struct_name #ty_generics #where_clause {
+                    type Item = <#inner as IntoIterator>::Item;
+                    type IntoIter = <#inner as IntoIterator>::IntoIter;
+
+                    fn into_iter(self) -> Self::IntoIter {
+                        self.0.into_iter()
+                    }
+                }
+
+                impl #ref_impl_generics IntoIterator for &'__a #struct_name #ty_generics #where_clause {
+                    type Item = <&'__a #inner as IntoIterator>::Item;
+                    type IntoIter = <&'__a #inner as IntoIterator>::IntoIter;
+
+                    fn into_iter(self) -> Self::IntoIter {
+                        self.0.iter()
+                    }
+                }
+
+                impl #impl_generics FromIterator<<#inner as IntoIterator>::Item> for #struct_name #ty_generics #collect_where_clause {
+                    fn from_iter<__I: IntoIterator<Item = <#inner as IntoIterator>::Item>>(iter: __I) -> Self {
+                        Self(iter.into_iter().collect())
+                    }
+                }
+
+                impl #impl_generics Extend<<#inner as IntoIterator>::Item> for #struct_name #ty_generics #collect_where_clause {
+                    fn extend<__I: IntoIterator<Item = <#inner as IntoIterator>::Item>>(&mut self, iter: __I) {
+                        self.0.extend(iter)
+                    }
+                }
+            },
+            Some("String") => quote! {
+                impl #impl_generics #struct_name #ty_generics #where_clause {
+                    pub fn len(&self) -> usize {
+                        self.0.len()
+                    }
+
+                    pub fn is_empty(&self) -> bool {
+                        self.0.is_empty()
+                    }
+
+                    pub fn chars(&self) -> std::str::Chars<'_> {
+                        self.0.chars()
+                    }
+                }
+            },
+            _ => TokenStream2::new(),
+        };
+        implementations.extend(collection_impl);
+    }
+
+    // `Deref` to the single field, or to `deref_target` through deref coercion
+    if options.deref {
+        if fields.len() != 1 {
+            panic!("deref requires a struct with exactly one field");
+        }
+        let member = &members[0];
+        let target = match &options.deref_target {
+            Some(target) => quote!(#target),
+            None => {
+                let ty = field_types[0];
+                quote!(#ty)
+            }
+        };
+        implementations.extend(quote! {
+            impl #impl_generics std::ops::Deref for #struct_name #ty_generics #where_clause {
+                type Target = #target;
+
+                fn deref(&self) -> &Self::Target {
+                    &self.#member
+                }
+            }
+        });
+    }
+
+    // `Add`/`Sub` between values and `Mul` by the inner scalar (arithmetic option)
+    if !options.arithmetic.is_empty() {
+        if !options.newtype {
+            panic!("arithmetic requires newtype = true");
+        }
+        let inner = field_types[0];
+        for op in &options.arithmetic {
+            let (trait_name, method, rhs_ty, rhs) = match op.as_str() {
+                "add" => (quote!(Add), quote!(add), quote!(Self), quote!(rhs.0)),
+                "sub" => (quote!(Sub), quote!(sub), quote!(Self), quote!(rhs.0)),
+                _ => (quote!(Mul), quote!(mul), quote!(#inner), quote!(rhs)),
+            };
+            let mut op_generics = input.generics.clone();
+            op_generics
+                .make_where_clause()
+                .predicates
+                .push(syn::parse_quote!(#inner: std::ops::#trait_name<Output = #inner>));
+            let op_where_clause = &op_generics.where_clause;
+            implementations.extend(quote! {
+                impl #impl_generics std::ops::#trait_name<#rhs_ty> for #struct_name #ty_generics #op_where_clause {
+                    type Output = Self;
+
+                    fn #method(self, rhs: #rhs_ty) -> Self {
+                        Self(std::ops::#trait_name::#method(self.0, #rhs))
+                    }
+                }
+            });
+        }
+
+        // `Sum` complements `Add`, over owned and borrowed values
+        if options.arithmetic.iter().any(|op| op == "add") {
+            let mut sum_generics = input.generics.clone();
+            sum_generics
+                .make_where_clause()
+                .predicates
+                .push(syn::parse_quote!(#inner: std::iter::Sum));
+            let sum_where_clause = &sum_generics.where_clause;
+            let mut ref_generics = input.generics.clone();
+            ref_generics.params.insert(0, syn::parse_quote!('__a));
+            ref_generics
+                .make_where_clause()
+                .predicates
+                .push(syn::parse_quote!(#inner: std::iter::Sum<&'__a #inner>));
+            let (ref_impl_generics, _, ref_where_clause) = ref_generics.split_for_impl();
+            implementations.extend(quote! {
+                impl #impl_generics std::iter::Sum for #struct_name #ty_generics #sum_where_clause {
+                    fn sum<__I: Iterator<Item = Self>>(iter: __I) -> Self {
+                        Self(iter.map(|value| value.0).sum())
+                    }
+                }
+
+                impl #ref_impl_generics std::iter::Sum<&'__a #struct_name #ty_generics> for #struct_name #ty_generics #ref_where_clause {
+                    fn sum<__I: Iterator<Item = &'__a Self>>(iter: __I) -> Self {
+                        Self(iter.map(|value| &value.0).sum())
+                    }
+                }
+            });
+        }
+    }
+
+    // field-wise `+=` for accumulator structs (add_assign option)
+    if options.add_assign {
+        if options.frozen {
+            panic!("add_assign cannot be used on frozen structs");
+        }
+        let summed: Vec<usize> = (0..tracked).filter(|&i| !is_marker[i]).collect();
+        let summed_members = summed.iter().map(|&i| &members[i]);
+        let mut add_generics = input.generics.clone();
+        let predicates = &mut add_generics.make_where_clause().predicates;
+        for &i in &summed {
+            let ty = field_types[i];
+            predicates.push(syn::parse_quote!(#ty: std::ops::AddAssign));
+        }
+        let add_where_clause = &add_generics.where_clause;
+        implementations.extend(quote! {
+            impl #impl_generics std::ops::AddAssign for #struct_name #ty_generics #add_where_clause {
+                fn add_assign(&mut self, rhs: Self) {
+                    #(self.#summed_members += rhs.#summed_members;)*
+                }
+            }
+        });
+    }
+
+    // `zero()` identity for accumulators, every field at its `Default`
+    // (zero option)
+    if options.zero {
+        let zeroed = field_names
+            .iter()
+            .zip(&field_types)
+            .zip(&field_options)
+            .filter(|(_, opts)| opts.compute.is_none())
+            .map(|((name, ty), _)| quote! { let #name: #ty = Default::default(); });
+        implementations.extend(quote! {
+            impl #impl_generics #struct_name #ty_generics #where_clause {
+                pub fn zero() -> Self {
+                    #(#zeroed)*
+                    #(#computed)*
+                    Self {
+                        #(#members: #field_names,)*
+                    }
+                }
+            }
+        });
+    }
+
+    // `Borrow<str>` for `String` newtypes used as map keys (borrow_str
+    // option); only sound while eq and hash are the derived ones, which
+    // agree with `str`'s
+    if options.borrow_str {
+        if !options.newtype
+            || !matches!(type_ident(field_types[0]), Some(ident) if ident == "String")
+        {
+            panic!("borrow_str requires newtype = true over a String");
+        }
+        if !derive_eq || !derive_hash {
+            panic!("borrow_str requires the default eq and hash of the inner String");
+        }
+        implementations.extend(quote! {
+            impl #impl_generics std::borrow::Borrow<str> for #struct_name #ty_generics #where_clause {
+                fn borrow(&self) -> &str {
+                    &self.0
+                }
+            }
+        });
+    }
+
+    // `!`, `&` and `|` for flag newtypes over `bool` (bool_ops option)
+    if options.bool_ops {
+        if !options.newtype || !matches!(type_ident(field_types[0]), Some(ident) if ident == "bool")
+        {
+            panic!("bool_ops requires newtype = true over a bool");
+        }
+        implementations.extend(quote! {
+            impl #impl_generics std::ops::Not for #struct_name #ty_generics #where_clause {
+                type Output = Self;
+
+                fn not(self) -> Self {
+                    Self(!self.0)
+                }
+            }
+
+            impl #impl_generics std::ops::BitAnd for #struct_name #ty_generics #where_clause {
+                type Output = Self;
+
+                fn bitand(self, rhs: Self) -> Self {
+                    Self(self.0 & rhs.0)
+                }
+            }
+
+            impl #impl_generics std::ops::BitOr for #struct_name #ty_generics #where_clause {
+                type Output = Self;
+
+                fn bitor(self, rhs: Self) -> Self {
+                    Self(self.0 | rhs.0)
+                }
+            }
+        });
+    }
+
+    // borrowed `#struct_nameRef<'a>` view (view option)
+    if options.view {
+        let ref_name = format_ident!("{}Ref", struct_name);
+        let mut ref_generics = input.generics.clone();
+        ref_generics.params.insert(0, syn::parse_quote!('__a));
+        let (ref_impl_generics, ref_ty_generics, _) = ref_generics.split_for_impl();
+        let (field_names, field_types, members) = (
+            &field_names[..tracked],
+            &field_types[..tracked],
+            &members[..tracked],
+        );
+
+        let view_def = if is_tuple {
+            quote! {
+                #(#lint_attrs)*
+                #[derive(Clone, Copy)]
+                pub struct #ref_name #ref_generics (#(pub &'__a #field_types),*) #where_clause;
+            }
+        } else {
+            quote! {
+                #(#lint_attrs)*
+                #[derive(Clone, Copy)]
+                pub struct #ref_name #ref_generics #where_clause {
+                    #(pub #field_names: &'__a #field_types,)*
+                }
+            }
+        };
+
+        let view_impl = quote! {
+            impl #impl_generics #struct_name #ty_generics #where_clause {
+                pub fn as_ref<'__a>(&'__a self) -> #ref_name #ref_ty_generics {
+                    #ref_name {
+                        #(#members: &self.#members,)*
+                    }
+                }
+            }
+
+            impl #ref_impl_generics From<&'__a #struct_name #ty_generics> for #ref_name #ref_ty_generics #where_clause {
+                fn from(value: &'__a #struct_name #ty_generics) -> Self {
+                    value.as_ref()
+                }
+            }
+        };
+        companion_defs.extend(view_def);
+        implementations.extend(view_impl);
+    }
+
+    // `#struct_nameField` enum for runtime access by field (field_enum option);
+    // `set` parses strings, so only scalar and `String` fields accept it
+    if options.field_enum {
+        if is_tuple {
+            panic!("field_enum requires a struct with named fields");
+        }
+        let enum_name = format_ident!("{}Field", struct_name);
+        let (field_names, field_types, members) = (
+            &field_names[..tracked],
+            &field_types[..tracked],
+            &members[..tracked],
+        );
+        let variants: Vec<_> = field_names
+            .iter()
+            .map(|name| {
+                let camel: String = name
+                    .to_string()
+                    .split('_')
+                    .filter(|part| !part.is_empty())
+                    .map(|part| {
+                        let mut chars = part.chars();
+                        chars.next().map_or_else(String::new, |first| {
+                            first.to_uppercase().chain(chars).collect()
+                        })
+                    })
+                    .collect();
+                format_ident!("{}", camel)
+            })
+            .collect();
+        let parseable: Vec<bool> = field_types
+            .iter()
+            .map(|ty| {
+                matches!(
+                    type_ident(ty).map(|ident| ident.to_string()).as_deref(),
+                    Some(
+                        "String"
+                            | "bool"
+                            | "char"
+                            | "i8"
+                            | "i16"
+                            | "i32"
+                            | "i64"
+                            | "i128"
+                            | "isize"
+                            | "u8"
+                            | "u16"
+                            | "u32"
+                            | "u64"
+                            | "u128"
+                            | "usize"
+                            | "f32"
+                            | "f64"
+                    )
+                )
+            })
+            .collect();
+        let getters = members.iter().zip(&parseable).map(|(member, &parseable)| {
+            if parseable {
+                quote! { self.#member.to_string() }
+            } else {
+                quote! { format!("{:?}", self.#member) }
+            }
+        });
+        let setters = (0..tracked).map(|i| {
+            let member = &members[i];
+            if parseable[i] {
+                let ty = field_types[i];
+                let record = record_change(&field_names[i]);
+                quote! {
+                    self.#member = value.parse::<#ty>().map_err(|err| {
+                        format!("invalid value for field `{}`: {}", stringify!(#member), err)
+                    })?;
+                    #record
+                    #refresh_hash
+                    Ok(())
+                }
+            } else {
+                quote! {
+                    Err(format!("field `{}` cannot be set from a string", stringify!(#member)))
+                }
+            }
+        });
+
+        companion_defs.extend(quote! {
+            #(#lint_attrs)*
+            #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+            pub enum #enum_name {
+                #(#variants,)*
+            }
+        });
+        implementations.extend(quote! {
+            impl #mutator_impl_generics #struct_name #ty_generics #mutator_where_clause {
+                pub fn get(&self, field: #enum_name) -> String {
+                    match field {
+                        #(#enum_name::#variants => #getters,)*
+                    }
+                }
+
+                pub fn set(&mut self, field: #enum_name, value: &str) -> Result<(), String> {
+                    match field {
+                        #(#enum_name::#variants => { #setters })*
+                    }
+                }
+            }
+        });
+    }
+
+    // attributes generated for the emitted fields
+    // `#[field(default = expr)]` becomes the serde default too, through a
+    // hidden helper, so partial input gets the same values as `new`; a bare
+    // `default` maps to `#[serde(default)]`. Defaults that read other fields
+    // and fields with their own serde default are left alone
+    let serde_defaults: Vec<Option<TokenStream2>> = (0..fields.len())
+        .map(|i| {
+            let opts = &field_options[i];
+            if is_marker[i] || has_serde_container_option(&fields[i].attrs, "default") {
+                return None;
+            }
+            match &opts.default {
+                Some(Some(expr))
+                    if field_names
+                        .iter()
+                        .any(|name| mentions(expr.to_token_stream(), name)) =>
+                {
+                    None
+                }
+                Some(Some(_)) => {
+                    let path = format!("{}::__serde_default_{}", struct_name, field_names[i]);
+                    Some(quote! { #[cfg_attr(feature = "serde", serde(default = #path))] })
+                }
+                Some(None) => Some(quote! { #[cfg_attr(feature = "serde", serde(default))] }),
+                None => None,
+            }
+        })
+        .collect();
+    let default_helpers = (0..fields.len())
+        .filter(|&i| {
+            matches!(&field_options[i].default, Some(Some(_))) && serde_defaults[i].is_some()
+        })
+        .map(|i| {
+            let helper = format_ident!("__serde_default_{}", field_names[i]);
+            let (ty, value) = (field_types[i], field_options[i].default_value());
+            quote! {
+                #[doc(hidden)]
+                pub fn #helper() -> #ty {
+                    #value
+                }
+            }
+        })
+        .collect::<Vec<_>>();
+    if !default_helpers.is_empty() {
+        implementations.extend(quote! {
+            #[cfg(feature = "serde")]
+            impl #impl_generics #struct_name #ty_generics #where_clause {
+                #(#default_helpers)*
+            }
+        });
+    }
+
+    let field_attrs: Vec<_> = field_options
+        .iter()
+        .zip(fields.iter())
+        .zip(&serde_defaults)
+        .map(|((opts, field), serde_default)| {
+            let mut generated = TokenStream2::new();
+            for attr in field
+                .attrs
+                .iter()
+                .filter(|attr| attr.path().is_ident("serde"))
+            {
+                let meta = &attr.meta;
+                generated.extend(quote! {
+                    #[cfg_attr(feature = "serde", #meta)]
+                });
+            }
+            if opts.flatten {
+                generated.extend(quote! {
+                    #[cfg_attr(feature = "serde", serde(flatten))]
+                });
+            }
+            if opts.transient || opts.skip {
+                generated.extend(quote! {
+                    #[cfg_attr(feature = "serde", serde(skip))]
+                });
+            }
+            if let Some(serde_default) = serde_default {
+                generated.extend(serde_default.clone());
+            }
+            generated
+        })
+        .collect();
+
+    // (frozen option)
+    let field_vis: Vec<_> = field_options
+        .iter()
+        .map(|opts| {
+            if opts.getter_mut || options.properties {
+                TokenStream2::new()
+            } else if options.frozen {
+                quote! { pub(crate) }
+            } else {
+                quote! { pub }
+            }
+        })
+        .collect();
+
+    let struct_def = if is_tuple {
+        quote! {
+            pub struct #struct_name #generics (#(#field_attrs #field_vis #field_types),*) #where_clause;
+        }
+    } else {
+        quote! {
+            pub struct #struct_name #generics #where_clause {
+                #(#field_attrs #field_vis #field_names: #field_types,)*
+            }
+        }
+    };
+
+    // serde container attributes, placed after the injected derive; options
+    // the struct already sets in its own `#[serde(...)]` are left alone
+    let mut serde_options = Vec::new();
+    if options.serde_strict && !has_serde_container_option(attrs, "deny_unknown_fields") {
+        serde_options.push(quote!(deny_unknown_fields));
+    }
+    if let Some(tag) = &options.serde_tag {
+        if !has_serde_container_option(attrs, "tag") {
+            serde_options.push(quote!(tag = #tag));
+        }
+    }
+    if let Some(rule) = &options.serde_rename_all {
+        if !has_serde_container_option(attrs, "rename_all") {
+            serde_options.push(quote!(rename_all = #rule));
+        }
+    }
+    let serde_attrs = if serde_options.is_empty() {
+        TokenStream2::new()
+    } else {
         quote! {
-            #(pub #field_names: #field_types,)*
+            #[cfg_attr(feature = "serde", serde(#(#serde_options),*))]
         }
     };
 
+    // downstream crates must go through the generated constructors
+    let mut struct_attrs = if options.non_exhaustive {
+        quote! { #[non_exhaustive] }
+    } else {
+        TokenStream2::new()
+    };
+    // same layout as the one real field, for FFI; `PhantomData` markers are
+    // zero-sized and allowed next to it (transparent option)
+    if options.transparent {
+        if is_marker.iter().filter(|&&marker| !marker).count() != 1 {
+            panic!("transparent requires exactly one field besides PhantomData markers");
+        }
+        struct_attrs.extend(quote! { #[repr(transparent)] });
+    }
+
     let expanded = quote! {
-        #[derive(Clone)]
+        #[derive(#(#derives),*)]
         #(#attrs)*
-        pub struct #struct_name {
-            #struct_fields
+        #serde_attrs
+        #struct_attrs
+        #struct_def
+
+        #companion_defs
+
+        #(#lint_attrs)*
+        const _: () = {
+            #implementations
+        };
+    };
+
+    expanded
+}
+
+// enums get the std derives picked by the options, plus a `Display` impl
+// once any variant has `#[variant(display = "...")]`; the format string can
+// name the variant's fields (`{radius}`), or `{_0}`, `{_1}`, ... for tuple
+// variants
+fn implement_enum(mut input: DeriveInput, options: DataclassOptions) -> TokenStream2 {
+    let enum_name = &input.ident;
+    let variants = match &mut input.data {
+        Data::Enum(data_enum) => &mut data_enum.variants,
+        _ => unreachable!(),
+    };
+
+    let mut has_float = false;
+    let mut has_display = false;
+    let mut display_arms = Vec::new();
+    for variant in variants.iter_mut() {
+        let mut display = None;
+        for attr in variant
+            .attrs
+            .iter()
+            .filter(|attr| attr.path().is_ident("variant"))
+        {
+            let meta_list = attr
+                .parse_args_with(Punctuated::<Meta, Comma>::parse_terminated)
+                .unwrap_or_else(|err| panic!("Invalid variant attribute: {}", err));
+            for meta in meta_list {
+                match meta {
+                    Meta::NameValue(nv) if nv.path.is_ident("display") => {
+                        let ident = nv.path.get_ident().unwrap();
+                        display = Some(str_value(&nv.value, ident));
+                    }
+                    _ => panic!("Unknown variant option: {}", meta.path().to_token_stream()),
+                }
+            }
+        }
+        variant
+            .attrs
+            .retain(|attr| !attr.path().is_ident("variant"));
+
+        let ident = &variant.ident;
+        has_display |= display.is_some();
+        let display = display.unwrap_or_else(|| ident.to_string());
+        let pattern = match &variant.fields {
+            Fields::Named(fields_named) => {
+                let names = fields_named.named.iter().map(|field| &field.ident);
+                quote! { Self::#ident { #(#names),* } }
+            }
+            Fields::Unnamed(fields_unnamed) => {
+                let names = (0..fields_unnamed.unnamed.len()).map(|i| format_ident!("_{}", i));
+                quote! { Self::#ident(#(#names),*) }
+            }
+            Fields::Unit => quote! { Self::#ident },
+        };
+        display_arms.push(quote! { #pattern => write!(f, #display) });
+        has_float |= variant.fields.iter().any(|field| {
+            let ty = &field.ty;
+            contains_float(quote!(#ty))
+        });
+    }
+
+    let mut derives = vec![quote!(Clone)];
+    if options.repr && options.repr_style != ReprStyle::Custom {
+        derives.push(quote!(Debug));
+    }
+    if options.eq {
+        derives.push(quote!(PartialEq));
+        if !has_float {
+            derives.push(quote!(Eq));
+        }
+    }
+    if options.order {
+        derives.push(quote!(PartialOrd));
+        if !has_float {
+            derives.push(quote!(Ord));
+        }
+    }
+    if options.unsafe_hash {
+        derives.push(quote!(Hash));
+    }
+
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let display_impl = if has_display {
+        quote! {
+            impl #impl_generics std::fmt::Display for #enum_name #ty_generics #where_clause {
+                #[allow(unused_variables)]
+                fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                    match self {
+                        #(#display_arms,)*
+                    }
+                }
+            }
         }
+    } else {
+        TokenStream2::new()
+    };
+    let expanded = quote! {
+        #[derive(#(#derives),*)]
+        #input
 
-        #implementations
+        #display_impl
     };
 
-    TokenStream::from(expanded)
+    expanded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use syn::parse::Parser;
+
+    // the expansion of `#[dataclass(args)] item` as a token string
+    fn expand_str(args: &str, item: &str) -> String {
+        let args = Punctuated::<Meta, Comma>::parse_terminated
+            .parse_str(args)
+            .unwrap();
+        expand(args, syn::parse_str(item).unwrap()).to_string()
+    }
+
+    #[test]
+    fn inline_getters() {
+        let item = "struct Point { x: i32 }";
+        let inlined = expand_str("frozen = true, inline_getters = true", item);
+        assert!(inlined.contains("# [inline] pub fn x (& self) -> & i32"));
+        let plain = expand_str("frozen = true", item);
+        assert!(plain.contains("pub fn x (& self) -> & i32"));
+        assert!(!plain.contains("# [inline]"));
+    }
+
+    #[test]
+    fn getter_docs() {
+        let expanded = expand_str(
+            "frozen = true",
+            "struct Person { #[field(doc = \"Age in years\")] age: u32, name: String }",
+        );
+        assert!(expanded.contains("# [doc = \"Age in years\"] pub fn age (& self)"));
+        assert!(expanded.contains("} pub fn name (& self)"));
+    }
+
+    #[test]
+    #[should_panic(expected = "compare_with and approx_eq fields must not be hashed")]
+    fn hashed_compare_with() {
+        expand_str(
+            "unsafe_hash = true",
+            "struct User { #[field(compare_with = eq_ignore_case)] name: String }",
+        );
+    }
+
+    #[test]
+    fn hash_derived_with_eq_only() {
+        let item = "struct Pair { a: u32, b: u32 }";
+        assert!(expand_str("unsafe_hash = true", item)
+            .contains("derive (Clone , PartialEq , Eq , Hash"));
+        let manual = expand_str(
+            "unsafe_hash = true, derive_eq_from_ord = true, order = true",
+            item,
+        );
+        assert!(manual.contains("impl std :: hash :: Hash for Pair"));
+    }
+
+    #[test]
+    fn enum_options() {
+        let item = "enum Mode { Fast, Slow }";
+        let plain = expand_str("eq = true", item);
+        assert!(!plain.contains("Display"));
+        assert!(!plain.contains("compile_error"));
+        let json = expand_str("repr_style = \"json\"", item);
+        assert!(json.contains(
+            "compile_error ! { \"repr_style = \\\"json\\\" is not supported on enums\" }"
+        ));
+        assert!(!expand_str("repr_style = \"custom\"", item).contains("compile_error"));
+        let rejected = expand_str("frozen = true, order = true", item);
+        assert!(rejected.contains("compile_error ! { \"`frozen` is not supported on enums\" }"));
+        assert!(!rejected.contains("`order`"));
+        let displayed = expand_str(
+            "",
+            "enum Mode { #[variant(display = \"fast\")] Fast, Slow }",
+        );
+        assert!(displayed.contains("impl std :: fmt :: Display for Mode"));
+    }
+
+    // the `arbitrary` crate is the calling crate's dependency, so the impl is
+    // only checked as tokens here
+    #[test]
+    fn arbitrary_impl() {
+        let expanded = expand_str(
+            "arbitrary = true",
+            "struct Order { id: u32, #[field(default = 1)] quantity: u8 }",
+        );
+        assert!(expanded.contains(
+            "# [cfg (feature = \"arbitrary\")] impl < '__a > :: arbitrary :: Arbitrary < '__a > for Order"
+        ));
+        assert!(expanded.contains("let id : u32 = :: arbitrary :: Arbitrary :: arbitrary (__u) ?"));
+        assert!(expanded.contains("let quantity : u8 = 1 ;"));
+    }
+
+    // `serde_json` belongs to the calling crate as well
+    #[test]
+    fn json_value_impl() {
+        let expanded = expand_str(
+            "json_value = true",
+            "struct Config { host: String, #[field(default = 3)] retries: u8 }",
+        );
+        assert!(expanded.contains(
+            "# [cfg (feature = \"serde_json\")] impl TryFrom < :: serde_json :: Value > for Config"
+        ));
+        assert!(
+            expanded.contains("None => return Err (\"Config.host: missing field\" . to_string ())")
+        );
+        assert!(expanded.contains("\"Config.retries\" , err)) ? , None => 3 ,"));
+    }
+
+    #[test]
+    fn into_json_impl() {
+        let expanded = expand_str("into_json = true", "struct Event { kind: String }");
+        assert!(expanded.contains(
+            "# [cfg (feature = \"serde_json\")] impl From < Event > for :: serde_json :: Value"
+        ));
+        assert!(expanded.contains(
+            ":: serde_json :: to_value (& value) . expect (\"Event failed to serialize to JSON\")"
+        ));
+    }
 }
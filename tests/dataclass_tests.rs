@@ -94,3 +94,2560 @@ fn test_default_options() {
     println!("{:?}", simple); // Should work due to default repr = true
     assert_eq!(simple, Simple::new(42)); // Should work due to default eq = true
 }
+
+#[test]
+fn test_computed_field() {
+    #[dataclass]
+    struct Name {
+        first: String,
+        last: String,
+        #[field(compute = format!("{} {}", first, last))]
+        full_name: String,
+    }
+
+    let name = Name::new(String::from("Ada"), String::from("Lovelace"));
+    assert_eq!(name.full_name, "Ada Lovelace");
+    assert_eq!(name.first, "Ada");
+    assert_eq!(name.last, "Lovelace");
+}
+
+#[test]
+fn test_computed_field_binding_order() {
+    #[dataclass]
+    struct Invoice {
+        net: u32,
+        #[field(default = 20)]
+        tax_percent: u32,
+        #[field(compute = net * tax_percent / 100)]
+        tax: u32,
+        #[field(compute = net + tax)]
+        total: u32,
+    }
+
+    let invoice = Invoice::new(150);
+    assert_eq!((invoice.net, invoice.tax_percent), (150, 20));
+    assert_eq!((invoice.tax, invoice.total), (30, 180));
+}
+
+#[test]
+fn test_sort_key() {
+    #[dataclass(order = true)]
+    struct Player {
+        name: String,
+        #[field(sort_key)]
+        score: u32,
+    }
+
+    let mut players = [
+        Player::new(String::from("Alice"), 30),
+        Player::new(String::from("Bob"), 10),
+        Player::new(String::from("Carol"), 20),
+    ];
+    players.sort();
+
+    let names: Vec<_> = players.iter().map(|p| p.name.as_str()).collect();
+    assert_eq!(names, ["Bob", "Carol", "Alice"]);
+}
+
+#[test]
+fn test_forwarded_lint_attributes() {
+    #[dataclass]
+    #[allow(dead_code)]
+    struct Unused {
+        value: i32,
+    }
+
+    // nothing generated for a deprecated struct, companions included, may
+    // warn; only the uses below need an allow. The serde derive is not ours
+    // and still warns on its own
+    #[cfg_attr(not(feature = "serde"), deny(warnings))]
+    #[cfg_attr(feature = "serde", allow(deprecated))]
+    mod legacy {
+        use dataclass_macro::dataclass;
+
+        #[dataclass(kw_only = true, view = true, field_enum = true)]
+        #[deprecated(note = "use Point instead")]
+        pub struct OldPoint {
+            pub x: i32,
+        }
+    }
+
+    #[allow(deprecated)]
+    let old = legacy::OldPoint::builder().x(1).build().unwrap();
+    #[allow(deprecated)]
+    let x = *old.as_ref().x;
+    assert_eq!(x, 1);
+}
+
+#[test]
+fn test_flatten_field() {
+    #[dataclass]
+    struct Address {
+        city: String,
+        street: String,
+    }
+
+    #[dataclass]
+    struct Customer {
+        name: String,
+        #[field(flatten)]
+        address: Address,
+    }
+
+    let customer = Customer::new(
+        String::from("Alice"),
+        Address::new(String::from("Paris"), String::from("Rue de Rivoli")),
+    );
+    assert_eq!(customer.address.city, "Paris");
+
+    #[cfg(feature = "serde")]
+    {
+        use serde::de::value::{Error, MapDeserializer};
+        use serde::Deserialize;
+
+        let fields = vec![
+            ("name", "Alice"),
+            ("city", "Paris"),
+            ("street", "Rue de Rivoli"),
+        ];
+        let flat =
+            Customer::deserialize(MapDeserializer::<_, Error>::new(fields.into_iter())).unwrap();
+        assert_eq!(flat, customer);
+    }
+}
+
+#[test]
+fn test_collection_newtype() {
+    #[dataclass(newtype = true)]
+    struct Tags(Vec<String>);
+
+    #[dataclass(newtype = true)]
+    struct Label(String);
+
+    let tags = Tags::new(vec![String::from("rust"), String::from("macro")]);
+    assert_eq!(tags.len(), 2);
+    assert!(!tags.is_empty());
+    let joined: Vec<_> = tags.iter().map(String::as_str).collect();
+    assert_eq!(joined, ["rust", "macro"]);
+    assert_eq!(format!("{:?}", tags), r#"Tags(["rust", "macro"])"#);
+
+    let label = Label::new(String::from("abc"));
+    assert_eq!(label.len(), 3);
+    assert_eq!(label.chars().rev().collect::<String>(), "cba");
+}
+
+#[test]
+fn test_newtype_into_iterator() {
+    #[dataclass(newtype = true)]
+    struct Tags(Vec<String>);
+
+    let tags = Tags::new(vec![String::from("a"), String::from("b")]);
+
+    let mut borrowed = Vec::new();
+    for tag in &tags {
+        borrowed.push(tag.clone());
+    }
+    assert_eq!(borrowed, ["a", "b"]);
+
+    let mut owned = Vec::new();
+    for tag in tags {
+        owned.push(tag);
+    }
+    assert_eq!(owned, ["a", "b"]);
+}
+
+#[test]
+fn test_grouped_derive_options() {
+    #[dataclass(derive(eq, order, hash))]
+    struct Version {
+        major: u32,
+        minor: u32,
+    }
+
+    let v1 = Version::new(1, 2);
+    let v2 = Version::new(1, 3);
+    assert!(v1 < v2);
+    assert_ne!(v1, v2);
+
+    use std::collections::HashSet;
+    let set: HashSet<_> = [v1.clone(), v1.clone(), v2].into_iter().collect();
+    assert_eq!(set.len(), 2);
+    assert!(set.contains(&v1));
+}
+
+#[test]
+fn test_functional_with_methods() {
+    #[dataclass(frozen = true, functional = true)]
+    struct Point {
+        x: i32,
+        y: i32,
+    }
+
+    let origin = Point::new(0, 0);
+    let moved = origin.clone().with_x(1).with_y(2);
+    assert_eq!(moved, Point::new(1, 2));
+    assert_eq!(origin, Point::new(0, 0));
+}
+
+#[test]
+fn test_generic_debug_bounds() {
+    #[dataclass]
+    struct Wrapper<T> {
+        value: T,
+    }
+
+    #[dataclass]
+    struct Borrowed<'a> {
+        name: &'a str,
+    }
+
+    #[derive(Clone)]
+    struct NotDebug;
+
+    // constructing with a non-Debug type is fine; only `{:?}` needs the bound
+    let wrapped = Wrapper::new(NotDebug);
+    let _copy = wrapped.clone();
+
+    assert_eq!(format!("{:?}", Wrapper::new(7)), "Wrapper { value: 7 }");
+
+    let name = String::from("Alice");
+    let borrowed = Borrowed::new(&name);
+    assert_eq!(format!("{:?}", borrowed), r#"Borrowed { name: "Alice" }"#);
+    assert_eq!(borrowed, Borrowed::new("Alice"));
+
+    #[dataclass(newtype = true)]
+    struct Bag<T>(Vec<T>);
+
+    let bag = Bag::new(vec![1, 2, 3]);
+    assert_eq!((&bag).into_iter().sum::<i32>(), 6);
+    assert_eq!(format!("{:?}", bag), "Bag([1, 2, 3])");
+}
+
+#[test]
+fn test_compare_and_hash_exclusion() {
+    #[dataclass(unsafe_hash = true, eq_hash_consistency = "strict")]
+    struct Entry {
+        key: String,
+        #[field(compare = false)]
+        hits: u32,
+    }
+
+    use std::collections::HashSet;
+    let first = Entry::new(String::from("a"), 1);
+    let second = Entry::new(String::from("a"), 2);
+    assert_eq!(first, second);
+
+    let set: HashSet<_> = [first].into_iter().collect();
+    assert!(set.contains(&second));
+}
+
+#[test]
+fn test_field_info() {
+    #[dataclass]
+    struct Person {
+        name: String,
+        age: i32,
+        email: Option<String>,
+    }
+
+    assert_eq!(
+        Person::field_info(),
+        [
+            ("name", std::any::type_name::<String>()),
+            ("age", "i32"),
+            ("email", std::any::type_name::<Option<String>>()),
+        ]
+    );
+
+    #[dataclass(newtype = true)]
+    struct Meters(f64);
+
+    assert_eq!(Meters::field_info(), [("0", "f64")]);
+}
+
+#[test]
+fn test_validate_range() {
+    #[dataclass]
+    struct Person {
+        name: String,
+        #[field(validate_range = 0..150)]
+        age: i32,
+        #[field(validate_range = 1..=10)]
+        rating: u8,
+    }
+
+    assert!(Person::try_new(String::from("Alice"), 30, 10).is_ok());
+
+    let err = Person::try_new(String::from("Bob"), 200, 5).unwrap_err();
+    assert_eq!(err, "age must be in range 0..150, got 200");
+
+    let person = Person::new(String::from("Carol"), 40, 0);
+    assert!(person.validate().unwrap_err().starts_with("rating"));
+}
+
+#[test]
+fn test_by_ptr_field() {
+    use std::collections::HashSet;
+    use std::rc::Rc;
+    use std::sync::Arc;
+
+    #[dataclass(unsafe_hash = true)]
+    struct CacheKey {
+        #[field(by_ptr)]
+        data: Arc<String>,
+    }
+
+    let shared = Arc::new(String::from("payload"));
+    let a = CacheKey::new(Arc::clone(&shared));
+    let b = CacheKey::new(Arc::clone(&shared));
+    let c = CacheKey::new(Arc::new(String::from("payload")));
+
+    assert_eq!(a, b);
+    assert_ne!(a, c);
+
+    let set: HashSet<_> = [a].into_iter().collect();
+    assert!(set.contains(&b));
+
+    #[dataclass(order = true)]
+    struct Node {
+        #[field(by_ptr)]
+        value: Rc<i32>,
+    }
+
+    assert_ne!(Node::new(Rc::new(1)), Node::new(Rc::new(1)));
+}
+
+#[test]
+fn test_frozen_getters() {
+    #[dataclass(frozen = true, inline_getters = true)]
+    struct Point {
+        x: i32,
+        y: i32,
+    }
+
+    #[dataclass(frozen = true)]
+    struct Label {
+        text: String,
+    }
+
+    let point = Point::new(3, 4);
+    assert_eq!(*point.x() + *point.y(), 7);
+    assert_eq!(Label::new(String::from("hi")).text(), "hi");
+}
+
+#[test]
+fn test_positional_index() {
+    #[dataclass(index = true)]
+    struct Rgb(u8, u8, u8);
+
+    let color = Rgb::new(255, 128, 0);
+    assert_eq!(color[0], 255);
+    assert_eq!(color[1], 128);
+    assert_eq!(color[2], 0);
+
+    let result = std::panic::catch_unwind(|| color[3]);
+    assert!(result.is_err());
+}
+
+#[test]
+fn test_added_in_constructors() {
+    #[dataclass]
+    struct Settings {
+        name: String,
+        #[field(added_in = "2.0")]
+        retries: u32,
+        #[field(added_in = "2.1")]
+        verbose: bool,
+    }
+
+    let old = Settings::new(String::from("app"));
+    assert_eq!(old.retries, 0);
+    assert!(!old.verbose);
+
+    let v2 = Settings::new_v2_0(String::from("app"), 3);
+    assert_eq!(v2.retries, 3);
+    assert!(!v2.verbose);
+
+    let current = Settings::new_v2_1(String::from("app"), 3, true);
+    assert!(current.verbose);
+}
+
+#[test]
+fn test_merge() {
+    #[dataclass(merge = true)]
+    struct Config {
+        host: String,
+        port: u16,
+        #[field(merge_skip)]
+        source: String,
+    }
+
+    let mut config = Config::new(String::from("localhost"), 80, String::from("defaults"));
+    let overrides = Config::new(String::from("example.com"), 8080, String::from("cli"));
+    config.merge(overrides);
+
+    assert_eq!(
+        config,
+        Config::new(String::from("example.com"), 8080, String::from("defaults"))
+    );
+}
+
+#[test]
+fn test_from_env() {
+    #[dataclass(env = true)]
+    struct ServerConfig {
+        #[field(env = "DATACLASS_TEST_HOST", default = String::from("localhost"))]
+        host: String,
+        #[field(env = "DATACLASS_TEST_PORT", default = 80)]
+        port: u16,
+        #[field(env = "DATACLASS_TEST_WORKERS")]
+        workers: usize,
+    }
+
+    std::env::set_var("DATACLASS_TEST_PORT", "8080");
+    std::env::set_var("DATACLASS_TEST_WORKERS", "4");
+    let config = ServerConfig::from_env().unwrap();
+    assert_eq!(config.host, "localhost");
+    assert_eq!(config.port, 8080);
+    assert_eq!(config.workers, 4);
+
+    std::env::set_var("DATACLASS_TEST_WORKERS", "many");
+    assert!(ServerConfig::from_env().unwrap_err().contains("workers"));
+
+    std::env::remove_var("DATACLASS_TEST_WORKERS");
+    assert!(ServerConfig::from_env().is_err());
+
+    // defaulted fields are not constructor parameters
+    let config = ServerConfig::new(2);
+    assert_eq!((config.host.as_str(), config.port), ("localhost", 80));
+}
+
+#[test]
+fn test_hash_subset() {
+    use std::collections::hash_map::DefaultHasher;
+    use std::hash::{Hash, Hasher};
+
+    #[dataclass(unsafe_hash = true, hash_subset = true)]
+    struct Entity {
+        #[field(id)]
+        id: u64,
+        name: String,
+        payload: Vec<u8>,
+    }
+
+    fn hash_of<T: Hash>(value: &T) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        value.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    let a = Entity::new(1, String::from("a"), vec![0; 1024]);
+    let b = Entity::new(1, String::from("a"), vec![0; 1024]);
+    let renamed = Entity::new(1, String::from("b"), vec![]);
+
+    // equal values hash equal
+    assert_eq!(a, b);
+    assert_eq!(hash_of(&a), hash_of(&b));
+
+    // only the id is hashed, but eq still sees every field
+    assert_ne!(a, renamed);
+    assert_eq!(hash_of(&a), hash_of(&renamed));
+    assert_eq!(hash_of(&a), hash_of(&1u64));
+}
+
+#[test]
+fn test_complex_field_types() {
+    use std::collections::{BTreeMap, HashSet};
+
+    #[dataclass(unsafe_hash = true, order = true)]
+    struct Index {
+        by_name: std::collections::BTreeMap<String, Vec<i32>>,
+        pairs: Vec<(u8, Option<String>)>,
+        grid: [[u8; 2]; 2],
+        first: <Vec<u8> as IntoIterator>::Item,
+    }
+
+    let mut by_name = BTreeMap::new();
+    by_name.insert(String::from("a"), vec![1, 2]);
+    let index = Index::new(
+        by_name,
+        vec![(1, Some(String::from("x"))), (2, None)],
+        [[1, 2], [3, 4]],
+        7,
+    );
+
+    let copy = index.clone();
+    assert_eq!(index, copy);
+    assert_eq!(index.cmp(&copy), std::cmp::Ordering::Equal);
+    assert_eq!(index.first, 7);
+    assert!(format!("{:?}", index).contains(r#"by_name: {"a": [1, 2]}"#));
+
+    let set: HashSet<_> = [index].into_iter().collect();
+    assert!(set.contains(&copy));
+
+    #[dataclass]
+    struct Nested {
+        lookup: std::collections::HashMap<String, Vec<Option<i32>>>,
+    }
+
+    let nested = Nested::new(Default::default());
+    assert_eq!(nested, Nested::new(Default::default()));
+    assert_eq!(format!("{:?}", nested), "Nested { lookup: {} }");
+}
+
+#[test]
+fn test_json_repr_style() {
+    #[dataclass(repr_style = "json")]
+    struct Person {
+        name: String,
+        age: i32,
+    }
+
+    let person = Person::new(String::from("Alice"), 30);
+    assert_eq!(format!("{:?}", person), r#"{"name": "Alice", "age": 30}"#);
+
+    #[dataclass(repr_style = "json")]
+    struct Pair(String, i32);
+
+    let pair = Pair::new(String::from("Bob"), 7);
+    assert_eq!(format!("{:?}", pair), r#"["Bob", 7]"#);
+}
+
+#[test]
+fn test_preset() {
+    use std::collections::BTreeSet;
+
+    #[dataclass(preset = "frozen_entity")]
+    struct Sku {
+        code: String,
+        revision: u32,
+    }
+
+    let a = Sku::new(String::from("A-1"), 2);
+    let b = Sku::new(String::from("A-1"), 1);
+    assert!(b < a);
+    assert_eq!(a.revision(), &2);
+
+    let set: BTreeSet<_> = [a.clone(), b, a].into_iter().collect();
+    assert_eq!(set.len(), 2);
+
+    #[dataclass(preset = "ordered", order = false)]
+    struct Unordered {
+        value: i32,
+    }
+
+    assert_eq!(Unordered::new(1), Unordered::new(1));
+}
+
+#[test]
+fn test_compare_with() {
+    fn eq_ignore_case(a: &str, b: &str) -> bool {
+        a.eq_ignore_ascii_case(b)
+    }
+
+    #[dataclass]
+    struct User {
+        #[field(compare_with = eq_ignore_case)]
+        name: String,
+        #[field(compare = false, compare_with = eq_ignore_case)]
+        nickname: String,
+        age: u32,
+    }
+
+    let a = User::new(String::from("Alice"), String::from("Al"), 30);
+    let b = User::new(String::from("ALICE"), String::from("Ally"), 30);
+    assert_eq!(a, b);
+    assert_ne!(a, User::new(String::from("Bob"), String::from("Al"), 30));
+}
+
+#[test]
+fn test_compare_with_order() {
+    fn eq_ignore_case(a: &str, b: &str) -> bool {
+        a.eq_ignore_ascii_case(b)
+    }
+
+    #[dataclass(order = true)]
+    struct Tag {
+        #[field(compare_with = eq_ignore_case)]
+        name: String,
+        weight: u32,
+    }
+
+    let lower = Tag::new(String::from("rust"), 1);
+    let upper = Tag::new(String::from("RUST"), 1);
+    assert_eq!(lower, upper);
+    assert_eq!(lower.cmp(&upper), std::cmp::Ordering::Equal);
+    assert!(Tag::new(String::from("RUST"), 0) < lower);
+    assert!(upper < Tag::new(String::from("zig"), 0));
+}
+
+#[test]
+fn test_non_exhaustive() {
+    // `#[non_exhaustive]` only blocks struct literals in other crates, so
+    // within this test crate we can only check that `new` still works
+    #[dataclass(non_exhaustive = true)]
+    struct Options {
+        verbose: bool,
+        level: u8,
+    }
+
+    let options = Options::new(true, 3);
+    assert!(options.verbose);
+    assert_eq!(options.level, 3);
+}
+
+#[test]
+fn test_const_defaults() {
+    #[dataclass(const_defaults = true)]
+    struct Limits {
+        #[field(default = 64)]
+        max_connections: u32,
+        #[field(default = 1.5)]
+        backoff: f64,
+        #[field(default = true)]
+        enabled: bool,
+    }
+
+    const LIMITS: Limits = Limits::DEFAULTS;
+    assert_eq!((LIMITS.max_connections, LIMITS.enabled), (64, true));
+    assert_eq!(LIMITS.backoff, 1.5);
+    assert_eq!(Limits::new(), LIMITS);
+}
+
+#[test]
+fn test_getter_docs() {
+    #[dataclass(frozen = true)]
+    struct Person {
+        #[field(doc = "The user's age in years")]
+        age: u32,
+        name: String,
+    }
+
+    let person = Person::new(42, String::from("Alice"));
+    assert_eq!(*person.age(), 42);
+    assert_eq!(person.name(), "Alice");
+}
+
+#[test]
+fn test_runtime_redaction() {
+    #[dataclass(runtime_redact = true)]
+    struct Credentials {
+        user: String,
+        #[field(sensitive)]
+        password: String,
+    }
+
+    let credentials = Credentials::new(String::from("alice"), String::from("hunter2"));
+    assert!(Credentials::is_redacted());
+    assert_eq!(
+        format!("{:?}", credentials),
+        r#"Credentials { user: "alice", password: "***" }"#
+    );
+
+    Credentials::set_redact(false);
+    assert_eq!(
+        format!("{:?}", credentials),
+        r#"Credentials { user: "alice", password: "hunter2" }"#
+    );
+    Credentials::set_redact(true);
+
+    #[dataclass]
+    struct Token {
+        #[field(sensitive)]
+        secret: String,
+    }
+
+    assert_eq!(
+        format!("{:?}", Token::new(String::from("abc"))),
+        r#"Token { secret: "***" }"#
+    );
+}
+
+#[test]
+fn test_reverse_ordering() {
+    #[dataclass(order = true)]
+    struct Task {
+        #[field(reverse)]
+        priority: u8,
+        name: String,
+    }
+
+    let mut tasks = [
+        Task::new(1, String::from("b")),
+        Task::new(3, String::from("c")),
+        Task::new(1, String::from("a")),
+        Task::new(2, String::from("d")),
+    ];
+    tasks.sort();
+
+    let order: Vec<_> = tasks
+        .iter()
+        .map(|t| (t.priority, t.name.as_str()))
+        .collect();
+    assert_eq!(order, [(3, "c"), (2, "d"), (1, "a"), (1, "b")]);
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_serde_strict() {
+    use serde::de::value::{Error, MapDeserializer};
+    use serde::Deserialize;
+
+    #[dataclass(serde_strict = true)]
+    struct Config {
+        host: String,
+    }
+
+    let known = vec![("host", "localhost")];
+    let config = Config::deserialize(MapDeserializer::<_, Error>::new(known.into_iter())).unwrap();
+    assert_eq!(config.host, "localhost");
+
+    let extra = vec![("host", "localhost"), ("port", "80")];
+    let result = Config::deserialize(MapDeserializer::<_, Error>::new(extra.into_iter()));
+    assert!(result
+        .unwrap_err()
+        .to_string()
+        .contains("unknown field `port`"));
+}
+
+#[test]
+fn test_partial_eq_only() {
+    #[dataclass(partial_eq_only = true)]
+    struct Reading {
+        sensor: String,
+        value: f64,
+    }
+
+    let a = Reading::new("temp".to_string(), 21.5);
+    assert_eq!(a, Reading::new("temp".to_string(), 21.5));
+    assert_ne!(a, Reading::new("temp".to_string(), 22.0));
+
+    let nan = Reading::new("temp".to_string(), f64::NAN);
+    assert_ne!(nan, nan.clone());
+}
+
+#[test]
+fn test_float_fields_skip_eq() {
+    fn assert_eq_impl<T: Eq>() {}
+
+    #[dataclass]
+    struct Point {
+        x: f64,
+        y: Option<f32>,
+    }
+
+    assert_eq!(Point::new(1.0, Some(2.0)), Point::new(1.0, Some(2.0)));
+    assert_ne!(Point::new(f64::NAN, None), Point::new(f64::NAN, None));
+
+    #[dataclass(force_eq = true)]
+    struct Score {
+        label: String,
+        value: f64,
+    }
+
+    assert_eq_impl::<Score>();
+
+    #[dataclass]
+    struct Tagged {
+        name: String,
+        #[field(compare = false)]
+        weight: f64,
+    }
+
+    assert_eq_impl::<Tagged>();
+}
+
+#[test]
+fn test_view() {
+    #[dataclass(view = true)]
+    struct Article {
+        title: String,
+        tags: Vec<String>,
+    }
+
+    fn title_len(view: ArticleRef<'_>) -> usize {
+        view.title.len()
+    }
+
+    let article = Article::new("Borrowing".to_string(), vec!["rust".to_string()]);
+    let view = article.as_ref();
+    assert_eq!(view.title, "Borrowing");
+    assert_eq!(view.tags, &["rust".to_string()]);
+    assert!(std::ptr::eq(view.title, &article.title));
+    assert_eq!(title_len(ArticleRef::from(&article)), 9);
+
+    #[dataclass(view = true)]
+    struct Pair<T>(T, T);
+
+    let pair = Pair::new(1, 2);
+    let PairRef(first, second) = pair.as_ref();
+    assert_eq!((*first, *second), (1, 2));
+}
+
+#[test]
+fn test_newtype_collect() {
+    use std::collections::HashSet;
+
+    #[dataclass(newtype = true)]
+    struct Tags(Vec<String>);
+
+    let mut tags: Tags = ["a", "b"].iter().map(|tag| tag.to_string()).collect();
+    tags.extend(["c".to_string()]);
+    assert_eq!(tags.0, ["a", "b", "c"]);
+
+    #[dataclass(newtype = true)]
+    struct Ids(HashSet<u32>);
+
+    let ids: Ids = [1, 2, 2, 3].into_iter().collect();
+    assert_eq!(ids.len(), 3);
+}
+
+#[test]
+fn test_debug_use_serde_names() {
+    #[dataclass(debug_use_serde_names = true)]
+    struct User {
+        #[serde(rename = "userName")]
+        user_name: String,
+        email: String,
+    }
+
+    let user = User::new("Alice".to_string(), "alice@example.com".to_string());
+    assert_eq!(
+        format!("{:?}", user),
+        "User { userName: \"Alice\", email: \"alice@example.com\" }"
+    );
+
+    #[cfg(feature = "serde")]
+    {
+        use serde::de::value::{Error, MapDeserializer};
+        use serde::Deserialize;
+
+        let entries = vec![("userName", "Bob"), ("email", "bob@example.com")];
+        let user =
+            User::deserialize(MapDeserializer::<_, Error>::new(entries.into_iter())).unwrap();
+        assert_eq!(user.user_name, "Bob");
+    }
+}
+
+#[test]
+fn test_hash_type_tag() {
+    use std::collections::hash_map::DefaultHasher;
+    use std::hash::{Hash, Hasher};
+
+    fn hash_of<T: Hash>(value: &T) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        value.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    #[dataclass(unsafe_hash = true, hash_type_tag = true)]
+    struct UserId(u64);
+
+    #[dataclass(unsafe_hash = true, hash_type_tag = true)]
+    struct OrderId(u64);
+
+    #[dataclass(unsafe_hash = true)]
+    struct PlainId(u64);
+
+    assert_eq!(hash_of(&UserId::new(7)), hash_of(&UserId::new(7)));
+    assert_ne!(hash_of(&UserId::new(7)), hash_of(&OrderId::new(7)));
+    assert_eq!(hash_of(&PlainId::new(7)), hash_of(&7u64));
+}
+
+#[test]
+fn test_lock_accessors() {
+    use std::sync::{Arc, Mutex, RwLock};
+
+    #[dataclass(eq = false, frozen = true)]
+    struct Shared {
+        name: String,
+        #[field(lock)]
+        hits: Arc<Mutex<u32>>,
+        #[field(lock)]
+        tags: Arc<RwLock<Vec<String>>>,
+    }
+
+    let shared = Shared::new("cache".to_string(), Arc::default(), Arc::default());
+    *shared.hits().unwrap() += 2;
+    shared.tags_mut().unwrap().push("hot".to_string());
+
+    assert_eq!(shared.name(), "cache");
+    assert_eq!(*shared.hits().unwrap(), 2);
+    assert_eq!(*shared.tags().unwrap(), ["hot"]);
+}
+
+#[test]
+fn test_lock_accessors_bare() {
+    use std::sync::{Mutex, RwLock};
+
+    #[dataclass(eq = false, properties = true)]
+    struct Counter {
+        label: String,
+        #[field(lock)]
+        count: Mutex<u32>,
+        #[field(lock)]
+        seen: RwLock<Vec<u32>>,
+    }
+
+    let mut counter = Counter::new("visits".to_string(), Mutex::new(1), RwLock::default());
+    *counter.count().unwrap() += 1;
+    counter.seen_mut().unwrap().push(7);
+    counter.set_label("hits".to_string());
+
+    assert_eq!(counter.label(), "hits");
+    assert_eq!(*counter.count().unwrap(), 2);
+    assert_eq!(*counter.seen().unwrap(), [7]);
+}
+
+#[test]
+fn test_default_panic() {
+    #[dataclass(default = true)]
+    struct Page {
+        #[field(default = 20)]
+        size: u32,
+        cursor: Option<String>,
+    }
+
+    let page = Page::default();
+    assert_eq!((page.size, page.cursor), (20, None));
+
+    #[dataclass(default_panic = true)]
+    struct Fixture {
+        #[field(default = 3)]
+        retries: u32,
+        endpoint: String,
+    }
+
+    let result = std::panic::catch_unwind(Fixture::default);
+    let message = *result.err().unwrap().downcast::<String>().unwrap();
+    assert_eq!(message, "field `endpoint` has no default");
+}
+
+#[test]
+fn test_field_tags() {
+    #[dataclass]
+    struct Heartbeat {
+        #[field(tag = 1)]
+        node: String,
+        #[field(tag = 3)]
+        sequence: u64,
+        local_only: bool,
+    }
+
+    let heartbeat = Heartbeat::new("db-1".to_string(), 42, true);
+    assert_eq!(
+        heartbeat.tagged_fields(),
+        [(1, "\"db-1\"".to_string()), (3, "42".to_string())]
+    );
+}
+
+#[test]
+fn test_getter_mut() {
+    #[dataclass]
+    struct Inventory {
+        owner: String,
+        #[field(getter_mut)]
+        items: Vec<String>,
+    }
+
+    let mut inventory = Inventory::new("Ada".to_string(), Vec::new());
+    inventory.items_mut().push("lamp".to_string());
+    inventory.owner.push_str(" L.");
+
+    assert_eq!(inventory.items(), &["lamp".to_string()]);
+    assert_eq!(inventory.owner, "Ada L.");
+}
+
+#[test]
+fn test_arithmetic_newtype() {
+    #[dataclass(newtype = true, arithmetic = true, order = true)]
+    struct Money(i64);
+
+    let total = Money::new(250) + Money::new(100) - Money::new(50);
+    assert_eq!(total, Money::new(300));
+    assert_eq!(total * 3, Money::new(900));
+
+    #[dataclass(newtype = true, arithmetic = "add")]
+    struct Meters(f64);
+
+    assert_eq!((Meters::new(1.5) + Meters::new(2.0)).0, 3.5);
+}
+
+#[test]
+fn test_newtype_sum() {
+    #[dataclass(newtype = true, arithmetic = "add")]
+    struct Money(i64);
+
+    let payments = vec![Money::new(100), Money::new(250), Money::new(-50)];
+    assert_eq!(payments.iter().sum::<Money>(), Money::new(300));
+    assert_eq!(payments.into_iter().sum::<Money>(), Money::new(300));
+    assert_eq!(
+        Vec::<Money>::new().into_iter().sum::<Money>(),
+        Money::new(0)
+    );
+}
+
+#[test]
+fn test_field_enum() {
+    #[dataclass(field_enum = true)]
+    struct Profile {
+        display_name: String,
+        age: u32,
+        tags: Vec<String>,
+    }
+
+    let mut profile = Profile::new("Ada".to_string(), 36, vec!["math".to_string()]);
+    profile.set(ProfileField::DisplayName, "Ada L.").unwrap();
+    profile.set(ProfileField::Age, "37").unwrap();
+
+    assert_eq!(profile.get(ProfileField::DisplayName), "Ada L.");
+    assert_eq!(profile.get(ProfileField::Age), "37");
+    assert_eq!(profile.get(ProfileField::Tags), "[\"math\"]");
+    assert!(profile.set(ProfileField::Age, "old").is_err());
+    assert!(profile.set(ProfileField::Tags, "x").is_err());
+    assert_eq!(profile.age, 37);
+}
+
+#[test]
+fn test_derived_hash_for_generics() {
+    use std::collections::hash_map::DefaultHasher;
+    use std::collections::HashSet;
+    use std::hash::{Hash, Hasher};
+
+    fn hash_of<T: Hash>(value: &T) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        value.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    #[dataclass(unsafe_hash = true)]
+    struct Pair<K, V> {
+        key: K,
+        value: V,
+    }
+
+    let pair = Pair::new("a".to_string(), 1u8);
+    assert_eq!(hash_of(&pair), hash_of(&Pair::new("a".to_string(), 1u8)));
+    assert_eq!(hash_of(&pair), hash_of(&("a".to_string(), 1u8)));
+
+    let set: HashSet<_> = [pair.clone(), pair].into_iter().collect();
+    assert_eq!(set.len(), 1);
+}
+
+#[test]
+fn test_derived_eq_for_generics() {
+    fn assert_eq_impl<T: Eq>() {}
+
+    // `T` needs no `Clone`/`Debug` for equality to work
+    #[derive(PartialEq, Eq)]
+    struct Opaque(u8);
+
+    #[dataclass(repr = false)]
+    struct Pair<T> {
+        left: T,
+        right: T,
+    }
+
+    assert!(Pair::new(Opaque(1), Opaque(2)) == Pair::new(Opaque(1), Opaque(2)));
+    assert!(Pair::new(Opaque(1), Opaque(2)) != Pair::new(Opaque(2), Opaque(1)));
+    assert_eq_impl::<Pair<Opaque>>();
+}
+
+#[test]
+fn test_phantom_data_fields() {
+    use std::marker::PhantomData;
+
+    struct Meters;
+    struct Feet;
+
+    #[dataclass(unsafe_hash = true)]
+    struct Length<Unit> {
+        value: u32,
+        unit: PhantomData<Unit>,
+    }
+
+    let length: Length<Meters> = Length::new(5);
+    assert_eq!(length.value, 5);
+    assert_eq!(format!("{:?}", length), "Length { value: 5 }");
+    assert_eq!(length, Length::new(5));
+
+    let feet: Length<Feet> = Length::new(3);
+    assert_eq!(feet.unit, PhantomData);
+}
+
+#[test]
+fn test_deref_target() {
+    #[dataclass(deref_target = str)]
+    struct Username(String);
+
+    let name = Username::new("ada_l".to_string());
+    assert_eq!(name.len(), 5);
+    assert!(name.starts_with("ada"));
+    assert_eq!(name.to_uppercase(), "ADA_L");
+
+    #[dataclass(deref = true)]
+    struct Scores {
+        values: Vec<u32>,
+    }
+
+    let scores = Scores::new(vec![3, 1, 2]);
+    assert_eq!(scores.iter().max(), Some(&3));
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_serde_container_options() {
+    use serde::de::value::{Error, MapDeserializer};
+    use serde::Deserialize;
+
+    #[dataclass(serde_rename_all = "camelCase", serde_deny_unknown = true)]
+    struct Account {
+        display_name: String,
+        home_page: String,
+    }
+
+    let entries = vec![("displayName", "Ada"), ("homePage", "ada.example")];
+    let account =
+        Account::deserialize(MapDeserializer::<_, Error>::new(entries.into_iter())).unwrap();
+    assert_eq!(account.display_name, "Ada");
+    assert_eq!(account.home_page, "ada.example");
+
+    let snake = vec![("display_name", "Ada"), ("home_page", "ada.example")];
+    assert!(Account::deserialize(MapDeserializer::<_, Error>::new(snake.into_iter())).is_err());
+}
+
+#[test]
+fn test_none_last_ordering() {
+    #[dataclass(order = true)]
+    struct Task {
+        #[field(none_last)]
+        due: Option<u32>,
+        name: &'static str,
+    }
+
+    let mut tasks = [
+        Task::new(None, "someday"),
+        Task::new(Some(5), "later"),
+        Task::new(Some(1), "soon"),
+        Task::new(None, "anytime"),
+    ];
+    tasks.sort();
+    let names: Vec<_> = tasks.iter().map(|task| task.name).collect();
+    assert_eq!(names, ["soon", "later", "anytime", "someday"]);
+
+    #[dataclass(order = true)]
+    struct Bid {
+        #[field(none_last, reverse)]
+        price: Option<u32>,
+    }
+
+    let mut bids = [Bid::new(None), Bid::new(Some(3)), Bid::new(Some(9))];
+    bids.sort();
+    let prices: Vec<_> = bids.iter().map(|bid| bid.price).collect();
+    assert_eq!(prices, [Some(9), Some(3), None]);
+}
+
+#[test]
+fn test_interned_fields() {
+    use std::sync::Arc;
+
+    #[dataclass]
+    struct LogLine {
+        #[field(intern)]
+        level: Arc<str>,
+        message: String,
+    }
+
+    let first = LogLine::new("warn", "disk at 91%".to_string());
+    let second = LogLine::new("warn", "disk at 93%".to_string());
+    let other = LogLine::new("info", "rotated".to_string());
+
+    assert_eq!(&*first.level, "warn");
+    assert!(Arc::ptr_eq(&first.level, &second.level));
+    assert!(!Arc::ptr_eq(&first.level, &other.level));
+}
+
+#[test]
+fn test_builder_maybe_setters() {
+    #[dataclass(kw_only = true)]
+    struct Request {
+        url: String,
+        #[field(default = 30)]
+        timeout_secs: u32,
+        user_agent: Option<String>,
+    }
+
+    fn build(timeout: Option<u32>, agent: Option<&str>) -> Result<Request, String> {
+        Request::builder()
+            .url("https://example.com".to_string())
+            .maybe_timeout_secs(timeout)
+            .user_agent(None)
+            .maybe_user_agent(agent.map(|agent| Some(agent.to_string())))
+            .build()
+    }
+
+    let request = build(None, Some("curl")).unwrap();
+    assert_eq!(request.timeout_secs, 30);
+    assert_eq!(request.user_agent.as_deref(), Some("curl"));
+
+    let request = build(Some(5), None).unwrap();
+    assert_eq!(request.timeout_secs, 5);
+    assert_eq!(request.user_agent, None);
+
+    let missing = Request::builder().maybe_url(None).build();
+    assert_eq!(missing.err().unwrap(), "missing field `url`");
+
+    // the positional constructor stays available next to the builder
+    let positional = Request::new("https://example.com".to_string(), None);
+    assert_eq!(positional, build(None, None).unwrap());
+}
+
+#[test]
+fn test_from_csv_row() {
+    #[dataclass(from_csv_row = ",")]
+    struct Employee {
+        name: String,
+        age: u32,
+        email: String,
+    }
+
+    let employee = Employee::try_from("Ada,36,ada@example.com").unwrap();
+    assert_eq!(
+        employee,
+        Employee::new("Ada".to_string(), 36, "ada@example.com".to_string())
+    );
+
+    assert_eq!(
+        Employee::try_from("Ada,old,ada@example.com").unwrap_err(),
+        "invalid value for field `age` at position 1: invalid digit found in string"
+    );
+    assert_eq!(
+        Employee::try_from("Ada,36").unwrap_err(),
+        "missing field `email` at position 2"
+    );
+    assert!(Employee::try_from("Ada,36,a@b,extra").is_err());
+}
+
+#[test]
+fn test_from_csv_row_marker() {
+    struct Meters;
+
+    #[dataclass(from_csv_row = ",")]
+    struct Length<U> {
+        value: u32,
+        unit: std::marker::PhantomData<U>,
+        label: String,
+    }
+
+    let length = Length::<Meters>::try_from("5,rope").unwrap();
+    assert_eq!((length.value, length.label.as_str()), (5, "rope"));
+    assert_eq!(length.to_csv_row(), "5,rope");
+}
+
+#[test]
+fn test_csv_round_trip() {
+    #[dataclass(from_csv_row = ";")]
+    struct Contact {
+        name: String,
+        note: String,
+        visits: u32,
+    }
+
+    let plain = Contact::new("Ada".to_string(), "math".to_string(), 3);
+    assert_eq!(plain.to_csv_row(), "Ada;math;3");
+
+    let tricky = Contact::new("Grace".to_string(), "said \"hi\"; left".to_string(), 7);
+    let row = tricky.to_csv_row();
+    assert_eq!(row, "Grace;\"said \"\"hi\"\"; left\";7");
+    assert_eq!(Contact::try_from(row.as_str()).unwrap(), tricky);
+    assert_eq!(
+        Contact::try_from(plain.to_csv_row().as_str()).unwrap(),
+        plain
+    );
+}
+
+#[test]
+fn test_debug_truncate() {
+    #[dataclass]
+    struct Batch {
+        id: u32,
+        #[field(debug_truncate = 3)]
+        samples: Vec<u32>,
+        #[field(debug_truncate = 3)]
+        labels: Vec<char>,
+    }
+
+    let batch = Batch::new(7, (0..1000).collect(), vec!['a', 'b']);
+    assert_eq!(
+        format!("{:?}", batch),
+        "Batch { id: 7, samples: [0, 1, 2, ...], labels: ['a', 'b'] }"
+    );
+}
+
+#[test]
+fn test_clone_modes() {
+    use std::rc::Rc;
+
+    #[dataclass(eq = false)]
+    struct Document {
+        #[field(clone = deep)]
+        body: Rc<String>,
+        #[field(clone = shallow)]
+        template: Rc<String>,
+    }
+
+    let original = Document::new(Rc::new("draft".to_string()), Rc::new("memo".to_string()));
+    let copy = original.clone();
+
+    assert_eq!(copy.body, original.body);
+    assert!(!Rc::ptr_eq(&copy.body, &original.body));
+    assert!(Rc::ptr_eq(&copy.template, &original.template));
+}
+
+#[test]
+fn test_track_changes() {
+    #[dataclass(track_changes = true)]
+    struct Settings {
+        theme: String,
+        font_size: u32,
+        autosave: bool,
+    }
+
+    let mut settings = Settings::new("light".to_string(), 12, false);
+    assert!(settings.take_changes().is_empty());
+
+    settings.set_font_size(14);
+    settings.set_theme("dark".to_string());
+    settings.set_font_size(16);
+    assert_eq!(settings.take_changes(), ["font_size", "theme"]);
+    assert!(settings.take_changes().is_empty());
+
+    assert_eq!(settings, Settings::new("dark".to_string(), 16, false));
+    assert_eq!(
+        format!("{:?}", settings),
+        "Settings { theme: \"dark\", font_size: 16, autosave: false }"
+    );
+}
+
+#[test]
+fn test_track_changes_bookkeeping() {
+    #[dataclass(track_changes = true, merge = true, functional = true, view = true)]
+    struct Profile {
+        name: String,
+        age: u32,
+    }
+
+    // the change log is not a field of the public API
+    assert_eq!(
+        Profile::field_info(),
+        [("name", "alloc::string::String"), ("age", "u32")]
+    );
+    assert_eq!(Profile::FIELD_COUNT, 2);
+
+    let mut profile = Profile::new("Ada".to_string(), 36).with_age(37);
+    assert_eq!(profile.take_changes(), ["age"]);
+
+    profile.set_age(38);
+    profile.merge(Profile::new("Grace".to_string(), 40));
+    assert_eq!(profile.take_changes(), ["age", "name"]);
+    assert_eq!(profile.as_ref().name, "Grace");
+}
+
+#[test]
+fn test_cache_hash() {
+    use std::collections::hash_map::DefaultHasher;
+    use std::collections::HashMap;
+    use std::hash::{Hash, Hasher};
+
+    #[dataclass(frozen = true, unsafe_hash = true, cache_hash = true)]
+    struct Route {
+        method: String,
+        path: String,
+        #[field(hash = false, compare = false)]
+        description: String,
+    }
+
+    let route = Route::new("GET".to_string(), "/users".to_string(), "list".to_string());
+    assert_eq!(route.cached_hash, route.recompute_hash());
+
+    let mut hasher = DefaultHasher::new();
+    route.hash(&mut hasher);
+    let mut expected = DefaultHasher::new();
+    expected.write_u64(route.recompute_hash());
+    assert_eq!(hasher.finish(), expected.finish());
+
+    let same = Route::new("GET".to_string(), "/users".to_string(), "other".to_string());
+    assert_eq!(route.cached_hash, same.cached_hash);
+
+    let mut hits = HashMap::new();
+    hits.insert(route, 1);
+    assert_eq!(hits.get(&same), Some(&1));
+    assert_eq!(
+        format!("{:?}", same),
+        "Route { method: \"GET\", path: \"/users\", description: \"other\" }"
+    );
+}
+
+#[test]
+fn test_cache_hash_after_updates() {
+    use std::collections::hash_map::DefaultHasher;
+    use std::hash::{Hash, Hasher};
+
+    #[dataclass(
+        frozen = true,
+        unsafe_hash = true,
+        cache_hash = true,
+        functional = true,
+        merge = true
+    )]
+    struct Key {
+        namespace: String,
+        id: u32,
+    }
+
+    fn hash_of(key: &Key) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        key.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    let moved = Key::new("users".to_string(), 1).with_id(2);
+    let fresh = Key::new("users".to_string(), 2);
+    assert_eq!(moved, fresh);
+    assert_eq!(hash_of(&moved), hash_of(&fresh));
+
+    let mut merged = Key::new("groups".to_string(), 7);
+    merged.merge(fresh.clone());
+    assert_eq!(merged, fresh);
+    assert_eq!(hash_of(&merged), hash_of(&fresh));
+}
+
+#[test]
+fn test_cache_hash_deref_eq() {
+    use std::collections::hash_map::DefaultHasher;
+    use std::hash::{Hash, Hasher};
+    use std::ops::Deref;
+
+    // a handle whose own equality and hash are identity, not contents
+    #[derive(Debug, Clone)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+    struct Handle {
+        id: u32,
+        value: String,
+    }
+
+    impl PartialEq for Handle {
+        fn eq(&self, other: &Self) -> bool {
+            self.id == other.id
+        }
+    }
+
+    impl Hash for Handle {
+        fn hash<H: Hasher>(&self, state: &mut H) {
+            self.id.hash(state);
+        }
+    }
+
+    impl Deref for Handle {
+        type Target = String;
+
+        fn deref(&self) -> &String {
+            &self.value
+        }
+    }
+
+    #[dataclass(frozen = true, unsafe_hash = true, cache_hash = true)]
+    struct Document {
+        #[field(deref_eq)]
+        body: Handle,
+    }
+
+    fn hash_of(document: &Document) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        document.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    let a = Document::new(Handle {
+        id: 1,
+        value: "hello".to_string(),
+    });
+    let b = Document::new(Handle {
+        id: 2,
+        value: "hello".to_string(),
+    });
+    assert_eq!(a, b);
+    assert_eq!(hash_of(&a), hash_of(&b));
+}
+
+#[test]
+fn test_bool_ops() {
+    #[dataclass(newtype = true, bool_ops = true)]
+    struct Enabled(bool);
+
+    let on = Enabled::new(true);
+    let off = Enabled::new(false);
+    assert_eq!(on.clone() & off.clone(), Enabled::new(false));
+    assert_eq!(on.clone() | off.clone(), Enabled::new(true));
+    assert_eq!(!off, Enabled::new(true));
+    assert_eq!(!on, Enabled::new(false));
+}
+
+#[test]
+fn test_frozen_map_iter() {
+    use std::collections::{BTreeMap, HashMap};
+
+    #[dataclass(frozen = true)]
+    struct Settings {
+        name: String,
+        values: HashMap<String, i32>,
+        ordered: BTreeMap<u8, char>,
+    }
+
+    let settings = Settings::new(
+        "app".to_string(),
+        HashMap::from([("a".to_string(), 1), ("b".to_string(), 2)]),
+        BTreeMap::from([(2, 'y'), (1, 'x')]),
+    );
+    let mut values: Vec<_> = settings
+        .values_iter()
+        .map(|(k, v)| (k.as_str(), *v))
+        .collect();
+    values.sort();
+    assert_eq!(values, [("a", 1), ("b", 2)]);
+    let ordered: Vec<_> = settings.ordered_iter().collect();
+    assert_eq!(ordered, [(&1, &'x'), (&2, &'y')]);
+    assert_eq!(settings.name(), "app");
+}
+
+mod consts {
+    pub const DEFAULT_TIMEOUT: u64 = 30;
+
+    pub fn default_host() -> String {
+        "localhost".to_string()
+    }
+}
+
+#[test]
+fn test_default_paths() {
+    #[dataclass(default = true)]
+    struct Client {
+        #[field(default = crate::consts::DEFAULT_TIMEOUT)]
+        timeout: u64,
+        #[field(default = consts::default_host())]
+        host: String,
+        port: u16,
+    }
+
+    let client = Client::default();
+    assert_eq!(
+        (client.timeout, client.host.as_str(), client.port),
+        (30, "localhost", 0)
+    );
+    let client = Client::new(8080);
+    assert_eq!(
+        (client.timeout, client.port),
+        (consts::DEFAULT_TIMEOUT, 8080)
+    );
+}
+
+#[test]
+fn test_approx_eq() {
+    #[dataclass]
+    struct Sample {
+        label: String,
+        #[field(approx_eq = 1e-9)]
+        readings: Vec<f64>,
+        #[field(approx_eq = 0.01)]
+        scale: f32,
+    }
+
+    let a = Sample::new("run".to_string(), vec![0.1 + 0.2, 1.0], 2.0);
+    let b = Sample::new("run".to_string(), vec![0.3, 1.0 + 1e-12], 2.001);
+    assert_eq!(a, b);
+
+    let off = Sample::new("run".to_string(), vec![0.3, 1.1], 2.0);
+    assert_ne!(a, off);
+    let shorter = Sample::new("run".to_string(), vec![0.3], 2.0);
+    assert_ne!(a, shorter);
+    let scaled = Sample::new("run".to_string(), vec![0.3, 1.0], 2.5);
+    assert_ne!(a, scaled);
+}
+
+mod properties {
+    use dataclass_macro::dataclass;
+
+    #[dataclass(properties = true)]
+    pub struct Temperature {
+        celsius: f64,
+        unit_label: String,
+    }
+}
+
+#[test]
+fn test_properties() {
+    use properties::Temperature;
+
+    let mut temperature = Temperature::new(21.5, "C".to_string());
+    assert_eq!(*temperature.celsius(), 21.5);
+    temperature.set_celsius(23.0);
+    temperature.set_unit_label("degrees C".to_string());
+    assert_eq!(*temperature.celsius(), 23.0);
+    assert_eq!(temperature.unit_label(), "degrees C");
+}
+
+#[test]
+fn test_enum_display() {
+    #[dataclass]
+    enum Shape {
+        #[variant(display = "circle of radius {radius}")]
+        Circle {
+            radius: u32,
+        },
+        #[variant(display = "{_0}x{_1} rectangle")]
+        Rect(u32, u32),
+        #[variant(display = "nothing")]
+        Empty,
+        Point,
+    }
+
+    assert_eq!(
+        Shape::Circle { radius: 3 }.to_string(),
+        "circle of radius 3"
+    );
+    assert_eq!(Shape::Rect(4, 5).to_string(), "4x5 rectangle");
+    assert_eq!(Shape::Empty.to_string(), "nothing");
+    assert_eq!(Shape::Point.to_string(), "Point");
+    assert_eq!(Shape::Rect(1, 2).clone(), Shape::Rect(1, 2));
+    assert_eq!(format!("{:?}", Shape::Empty), "Empty");
+}
+
+#[test]
+fn test_enum_own_display() {
+    #[dataclass(eq = true)]
+    enum Level {
+        Low,
+        High,
+    }
+
+    impl std::fmt::Display for Level {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            f.write_str(if *self == Level::Low { "low" } else { "high" })
+        }
+    }
+
+    assert_eq!(Level::High.to_string(), "high");
+    assert_eq!(Level::Low.to_string(), "low");
+}
+
+#[test]
+fn test_json_schema() {
+    #[dataclass(schema = true)]
+    struct Account {
+        id: u64,
+        name: String,
+        active: bool,
+        balance: f64,
+        nickname: Option<String>,
+        tags: Vec<String>,
+    }
+
+    assert_eq!(
+        Account::json_schema(),
+        concat!(
+            r#"{"title":"Account","type":"object","properties":{"#,
+            r#""id":{"type":"integer"},"name":{"type":"string"},"active":{"type":"boolean"},"#,
+            r#""balance":{"type":"number"},"nickname":{"anyOf":[{"type":"string"},{"type":"null"}]},"#,
+            r#""tags":{"type":"array","items":{"type":"string"}}},"#,
+            r#""required":["id","name","active","balance","tags"]}"#
+        )
+    );
+}
+
+#[test]
+fn test_composite_hash_key() {
+    use std::collections::hash_map::DefaultHasher;
+    use std::collections::HashSet;
+    use std::hash::{Hash, Hasher};
+
+    #[dataclass]
+    struct Seat {
+        #[field(hash_key)]
+        row: char,
+        #[field(hash_key)]
+        number: u32,
+        holder: String,
+    }
+
+    fn hash_of<T: Hash>(value: &T) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        value.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    let booked = Seat::new('C', 12, "Ana".to_string());
+    let resold = Seat::new('C', 12, "Ben".to_string());
+    assert_eq!(hash_of(&booked), hash_of(&resold));
+    assert_eq!(hash_of(&booked), hash_of(&('C', 12u32)));
+    assert_ne!(booked, resold);
+    assert_ne!(
+        hash_of(&booked),
+        hash_of(&Seat::new('C', 13, "Ana".to_string()))
+    );
+
+    let seats: HashSet<_> = [booked.clone(), resold, booked].into_iter().collect();
+    assert_eq!(seats.len(), 2);
+}
+
+#[test]
+fn test_mock() {
+    #[dataclass(mock = true)]
+    struct Person {
+        name: String,
+        age: u32,
+        email: Option<String>,
+        #[field(default = "member")]
+        role: &'static str,
+        #[field(compute = name.len())]
+        name_len: usize,
+        nick: &'static str,
+        avatar: &'static [u8],
+    }
+
+    let person = Person::mock();
+    assert_eq!((person.nick, person.avatar), ("test", &[][..]));
+    assert_eq!(
+        (
+            person.name.as_str(),
+            person.age,
+            person.email,
+            person.role,
+            person.name_len
+        ),
+        ("test", 0, None, "member", 4)
+    );
+}
+
+#[test]
+fn test_transient_field() {
+    #[dataclass(unsafe_hash = true)]
+    struct Document {
+        path: String,
+        #[field(transient)]
+        rendered: Option<String>,
+    }
+
+    let fresh = Document::new("a.md".to_string(), None);
+    let cached = Document::new("a.md".to_string(), Some("<p>a</p>".to_string()));
+    assert_eq!(fresh, cached);
+
+    #[cfg(feature = "serde")]
+    {
+        use serde::de::value::{Error, MapDeserializer};
+        use serde::Deserialize;
+
+        #[dataclass(serde_deny_unknown = true)]
+        struct Page {
+            title: String,
+            #[field(transient)]
+            html: String,
+        }
+
+        let entries = [("title", "Home")];
+        let page =
+            Page::deserialize(MapDeserializer::<_, Error>::new(entries.into_iter())).unwrap();
+        assert_eq!((page.title.as_str(), page.html.as_str()), ("Home", ""));
+
+        let with_html = [("title", "Home"), ("html", "<h1>Home</h1>")];
+        assert!(
+            Page::deserialize(MapDeserializer::<_, Error>::new(with_html.into_iter())).is_err()
+        );
+    }
+}
+
+#[test]
+fn test_newtype_from() {
+    #[dataclass(newtype = true)]
+    struct UserId(u64);
+
+    let id: UserId = 42u64.into();
+    assert_eq!(id, UserId::new(42));
+    let raw: u64 = id.into();
+    assert_eq!(raw, 42);
+
+    #[dataclass(newtype = true)]
+    struct Wrapper<T>(T);
+
+    let wrapped: Wrapper<&str> = "x".into();
+    assert_eq!(wrapped.0, "x");
+}
+
+#[test]
+fn test_derive_eq_from_ord() {
+    use std::cmp::Ordering;
+
+    #[dataclass(order = true, derive_eq_from_ord = true, unsafe_hash = true)]
+    struct Version {
+        #[field(sort_key)]
+        number: u32,
+        label: String,
+    }
+
+    let pairs = [
+        (
+            Version::new(0, "zero".to_string()),
+            Version::new(0, "none".to_string()),
+        ),
+        (
+            Version::new(u32::MAX, "max".to_string()),
+            Version::new(u32::MAX, "max".to_string()),
+        ),
+        (
+            Version::new(0, "a".to_string()),
+            Version::new(u32::MAX, "a".to_string()),
+        ),
+        (
+            Version::new(7, "b".to_string()),
+            Version::new(6, "b".to_string()),
+        ),
+    ];
+    for (a, b) in &pairs {
+        assert_eq!(a == b, a.cmp(b) == Ordering::Equal);
+        assert_eq!(b == a, b.cmp(a) == Ordering::Equal);
+    }
+    assert_eq!(pairs[0].0, pairs[0].1);
+    assert_ne!(pairs[2].0, pairs[2].1);
+
+    let set: std::collections::HashSet<_> = pairs.into_iter().flat_map(|(a, b)| [a, b]).collect();
+    assert_eq!(set.len(), 4);
+}
+
+#[test]
+fn test_where_clause() {
+    use std::collections::HashSet;
+    use std::ops::Range;
+
+    #[dataclass(unsafe_hash = true)]
+    struct Batch<T>
+    where
+        T: Iterator,
+        T::Item: Copy,
+    {
+        source: T,
+        // serde does not infer bounds for associated types
+        #[serde(skip)]
+        items: Vec<T::Item>,
+        #[field(compare = false)]
+        note: String,
+    }
+
+    let a: Batch<Range<u32>> = Batch::new(0..3, vec![1, 2], "first".to_string());
+    let b = Batch::new(0..3, vec![1, 2], "second".to_string());
+    let c = Batch::new(0..3, vec![1, 3], "first".to_string());
+    assert_eq!(a, b);
+    assert_ne!(a, c);
+    assert_eq!(a.source.clone().sum::<u32>(), 3);
+    assert_eq!(format!("{:?}", c.items), "[1, 3]");
+
+    let batches: HashSet<_> = [a, b, c].into_iter().collect();
+    assert_eq!(batches.len(), 2);
+}
+
+#[test]
+fn test_add_assign() {
+    use std::time::Duration;
+
+    #[dataclass(add_assign = true)]
+    struct Stats {
+        count: u32,
+        sum: f64,
+        elapsed: Duration,
+    }
+
+    let mut total = Stats::new(0, 0.0, Duration::ZERO);
+    for (value, millis) in [(1.5, 10), (2.5, 20), (4.0, 5)] {
+        total += Stats::new(1, value, Duration::from_millis(millis));
+    }
+    assert_eq!(total, Stats::new(3, 8.0, Duration::from_millis(35)));
+}
+
+#[test]
+fn test_zero() {
+    #[dataclass(add_assign = true, zero = true)]
+    struct Totals {
+        orders: u32,
+        revenue: i64,
+    }
+
+    let totals = [(2, 150), (1, -20), (4, 300)].into_iter().fold(
+        Totals::zero(),
+        |mut acc, (orders, revenue)| {
+            acc += Totals::new(orders, revenue);
+            acc
+        },
+    );
+    assert_eq!(totals, Totals::new(7, 430));
+    assert_eq!(Totals::zero(), Totals::new(0, 0));
+}
+
+#[test]
+fn test_debug_address() {
+    #[dataclass(debug_address = true)]
+    struct Node {
+        id: u32,
+    }
+
+    let node = Node::new(1);
+    let alias = &node;
+    let copy = node.clone();
+    let expected = format!("Node@{:p} {{ id: 1 }}", &node);
+    assert_eq!(format!("{:?}", node), expected);
+    assert_eq!(format!("{:?}", alias), expected);
+    assert_ne!(format!("{:?}", copy), expected);
+}
+
+#[test]
+fn test_positional_fields() {
+    #[dataclass]
+    struct Reading {
+        sensor: String,
+        value: i32,
+        #[field(sensitive)]
+        token: String,
+    }
+
+    let reading = Reading::new("t1".to_string(), -4, "secret".to_string());
+    assert_eq!(Reading::FIELD_COUNT, 3);
+    let shown: Vec<_> = (0..Reading::FIELD_COUNT)
+        .map(|i| format!("{:?}", reading.get_field(i).unwrap()))
+        .collect();
+    assert_eq!(shown, ["\"t1\"", "-4", "\"***\""]);
+    assert!(reading.get_field(3).is_none());
+    assert!(reading.get_field(usize::MAX).is_none());
+}
+
+#[test]
+fn test_borrow_str() {
+    use std::collections::HashMap;
+
+    #[dataclass(newtype = true, borrow_str = true)]
+    struct Key(String);
+
+    let mut scores = HashMap::new();
+    scores.insert(Key::new("ada".to_string()), 3);
+    scores.insert(Key::new("bob".to_string()), 5);
+    assert_eq!(scores.get("ada"), Some(&3));
+    assert_eq!(scores["bob"], 5);
+    assert_eq!(scores.get("eve"), None);
+}
+
+#[test]
+fn test_from_pairs() {
+    #[dataclass(from_pairs = true)]
+    struct Signup {
+        email: String,
+        age: u8,
+        #[field(default = true)]
+        newsletter: bool,
+    }
+
+    let signup = Signup::from_pairs(&[("age", "34"), ("email", "ada@example.com")]).unwrap();
+    assert_eq!(signup, Signup::new("ada@example.com".to_string(), 34));
+    assert!(signup.newsletter);
+
+    let opted_out = Signup::from_pairs(&[
+        ("email", "bob@example.com"),
+        ("age", "20"),
+        ("newsletter", "false"),
+    ])
+    .unwrap();
+    assert!(!opted_out.newsletter);
+
+    assert_eq!(
+        Signup::from_pairs(&[("email", "x@example.com")]).unwrap_err(),
+        "missing field `age`"
+    );
+    assert_eq!(
+        Signup::from_pairs(&[("email", "x@example.com"), ("age", "20"), ("phone", "1")])
+            .unwrap_err(),
+        "unknown field `phone`"
+    );
+    assert!(Signup::from_pairs(&[("email", "x"), ("age", "old")])
+        .unwrap_err()
+        .starts_with("invalid value for field `age`: "));
+}
+
+#[test]
+fn test_from_pairs_marker() {
+    struct Draft;
+
+    #[dataclass(from_pairs = true)]
+    struct Form<S> {
+        title: String,
+        state: std::marker::PhantomData<S>,
+    }
+
+    let form = Form::<Draft>::from_pairs(&[("title", "Notes")]).unwrap();
+    assert_eq!(form.title, "Notes");
+    assert_eq!(
+        Form::<Draft>::from_pairs(&[("title", "x"), ("state", "y")]).unwrap_err(),
+        "unknown field `state`"
+    );
+}
+
+#[test]
+fn test_transparent() {
+    use std::marker::PhantomData;
+    use std::mem::{align_of, size_of};
+
+    #[dataclass(newtype = true, transparent = true)]
+    struct Handle(u64);
+
+    #[dataclass(transparent = true)]
+    struct Meters<T> {
+        value: f32,
+        unit: PhantomData<T>,
+    }
+
+    assert_eq!(size_of::<Handle>(), size_of::<u64>());
+    assert_eq!(align_of::<Handle>(), align_of::<u64>());
+    assert_eq!(size_of::<Meters<String>>(), size_of::<f32>());
+    assert_eq!(Handle::new(9).0, 9);
+    assert_eq!(Meters::<()>::new(1.5).value, 1.5);
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_serde_field_defaults() {
+    use serde::de::value::{Error, MapDeserializer};
+    use serde::Deserialize;
+
+    #[dataclass]
+    struct Connection {
+        host: String,
+        #[field(default = 5432)]
+        port: u16,
+        #[field(default = String::from("public"))]
+        schema: String,
+        #[field(default)]
+        verbose: bool,
+    }
+
+    let entries = [("host", "db.local")];
+    let partial =
+        Connection::deserialize(MapDeserializer::<_, Error>::new(entries.into_iter())).unwrap();
+    assert_eq!(partial, Connection::new("db.local".to_string()));
+    assert_eq!(
+        (partial.port, partial.schema.as_str(), partial.verbose),
+        (5432, "public", false)
+    );
+
+    let entries = [("host", "db.local"), ("schema", "audit")];
+    let explicit =
+        Connection::deserialize(MapDeserializer::<_, Error>::new(entries.into_iter())).unwrap();
+    assert_eq!((explicit.port, explicit.schema.as_str()), (5432, "audit"));
+}
+
+#[test]
+fn test_newtype_as_mut() {
+    #[dataclass(newtype = true)]
+    struct Buffer(Vec<u8>);
+
+    fn append(target: &mut impl AsMut<Vec<u8>>, bytes: &[u8]) {
+        target.as_mut().extend_from_slice(bytes);
+    }
+
+    let mut buffer = Buffer::new(vec![1]);
+    append(&mut buffer, &[2, 3]);
+    buffer.as_mut().push(4);
+    assert_eq!(buffer.as_ref(), &[1, 2, 3, 4]);
+}
+
+#[test]
+fn test_matches() {
+    #[dataclass(matches = true)]
+    struct Listing {
+        #[field(key)]
+        city: String,
+        #[field(key)]
+        rooms: u8,
+        price: u32,
+    }
+
+    let wanted = Listing::new("Oslo".to_string(), 2, 0);
+    let offer = Listing::new("Oslo".to_string(), 2, 1800);
+    assert!(wanted.matches(&offer));
+    assert_ne!(wanted, offer);
+    assert!(!wanted.matches(&Listing::new("Oslo".to_string(), 3, 0)));
+
+    #[dataclass(matches = true)]
+    struct Tag {
+        name: String,
+        weight: u8,
+    }
+
+    let tag = Tag::new("rust".to_string(), 1);
+    assert!(tag.matches(&tag.clone()));
+    assert!(!tag.matches(&Tag::new("rust".to_string(), 2)));
+}
+
+#[test]
+fn test_derived_order() {
+    #[dataclass(order = true)]
+    struct Pair<A, B> {
+        first: A,
+        second: B,
+    }
+
+    let mut pairs = [
+        Pair::new("b", 1),
+        Pair::new("a", 9),
+        Pair::new("b", 0),
+        Pair::new("a", 2),
+    ];
+    pairs.sort();
+    let sorted: Vec<_> = pairs.iter().map(|pair| (pair.first, pair.second)).collect();
+    assert_eq!(sorted, [("a", 2), ("a", 9), ("b", 0), ("b", 1)]);
+    assert_eq!(
+        Pair::new(1, 'x').partial_cmp(&Pair::new(1, 'y')),
+        Some(std::cmp::Ordering::Less)
+    );
+    assert_eq!(Pair::new(1, 'x').max(Pair::new(0, 'z')), Pair::new(1, 'x'));
+}
+
+#[test]
+fn test_diff() {
+    #[dataclass(diff = true)]
+    struct ServerConfig {
+        host: String,
+        port: u16,
+        workers: u8,
+        #[field(repr = false)]
+        api_key: String,
+    }
+
+    let before = ServerConfig::new("localhost".to_string(), 8080, 4, "old".to_string());
+    let after = ServerConfig::new("localhost".to_string(), 9090, 8, "new".to_string());
+    assert_eq!(
+        before.diff(&after),
+        [
+            ("port", "8080".to_string(), "9090".to_string()),
+            ("workers", "4".to_string(), "8".to_string()),
+            ("api_key", "***".to_string(), "***".to_string()),
+        ]
+    );
+    assert!(before.diff(&before.clone()).is_empty());
+    assert_eq!(
+        format!("{:?}", before),
+        "ServerConfig { host: \"localhost\", port: 8080, workers: 4 }"
+    );
+}
+
+#[test]
+fn test_debug_with() {
+    #[dataclass(debug_with = true)]
+    struct Session {
+        user: String,
+        _scratch: Vec<u8>,
+        #[field(sensitive)]
+        token: String,
+        retries: u8,
+    }
+
+    let session = Session::new("ada".to_string(), vec![1, 2], "t0k".to_string(), 2);
+    assert_eq!(
+        session.debug_with(|name| name.starts_with(char::is_alphabetic)),
+        "Session { user: \"ada\", token: \"***\", retries: 2 }"
+    );
+    assert_eq!(
+        session.debug_with(|name| name == "retries"),
+        "Session { retries: 2 }"
+    );
+    assert_eq!(session.debug_with(|_| false), "Session");
+}
+
+#[test]
+fn test_float_hash() {
+    use std::collections::hash_map::DefaultHasher;
+    use std::collections::HashSet;
+    use std::hash::{Hash, Hasher};
+
+    #[dataclass(unsafe_hash = true, force_eq = true)]
+    struct Sample {
+        sensor: u8,
+        value: f64,
+        weight: f32,
+    }
+
+    let mut samples = HashSet::new();
+    samples.insert(Sample::new(1, 0.5, 1.0));
+    samples.insert(Sample::new(1, 0.5, 1.0));
+    samples.insert(Sample::new(1, 0.25, 1.0));
+    assert_eq!(samples.len(), 2);
+    assert!(samples.contains(&Sample::new(1, 0.25, 1.0)));
+
+    fn hash_of<T: Hash>(value: &T) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        value.hash(&mut hasher);
+        hasher.finish()
+    }
+    let (zero, negative_zero) = (Sample::new(2, 0.0, 0.0), Sample::new(2, -0.0, -0.0));
+    assert_eq!(zero, negative_zero);
+    assert_eq!(hash_of(&zero), hash_of(&negative_zero));
+}
+
+#[test]
+fn test_non_empty() {
+    #[dataclass]
+    struct Team {
+        #[field(non_empty)]
+        name: String,
+        #[field(non_empty)]
+        members: Vec<String>,
+    }
+
+    assert_eq!(
+        Team::try_new(String::new(), vec!["ada".to_string()]).unwrap_err(),
+        "name must not be empty"
+    );
+    assert_eq!(
+        Team::try_new("core".to_string(), Vec::new()).unwrap_err(),
+        "members must not be empty"
+    );
+    let team = Team::try_new("core".to_string(), vec!["ada".to_string()]).unwrap();
+    assert!(team.validate().is_ok());
+}
+
+#[test]
+fn test_builder_validate() {
+    fn check_email(email: &String) -> Result<(), String> {
+        if email.contains('@') {
+            Ok(())
+        } else {
+            Err(format!("{:?} is not an email address", email))
+        }
+    }
+
+    #[dataclass(kw_only = true)]
+    struct Account {
+        #[field(validate = check_email)]
+        email: String,
+        #[field(default = 0)]
+        logins: u32,
+    }
+
+    let err = Account::builder().email("nope".to_string()).err();
+    assert_eq!(
+        err.as_deref(),
+        Some("email: \"nope\" is not an email address")
+    );
+
+    let account = Account::builder()
+        .email("ada@example.com".to_string())
+        .unwrap()
+        .logins(2)
+        .build()
+        .unwrap();
+    assert_eq!(account.logins, 2);
+    assert!(account.validate().is_ok());
+    assert!(Account::builder()
+        .maybe_email(None)
+        .unwrap()
+        .build()
+        .is_err());
+    assert!(Account::try_new("nope".to_string()).is_err());
+}
+
+#[test]
+fn test_index_by_name() {
+    #[dataclass(index_by_name = true)]
+    struct Person {
+        name: String,
+        age: u32,
+        #[field(sensitive)]
+        password: String,
+    }
+
+    #[dataclass(index_by_name = true)]
+    struct Scores {
+        home: u32,
+        away: u32,
+    }
+
+    let person = Person::new("Ada".to_string(), 36, "hunter2".to_string());
+    assert_eq!(format!("{:?}", &person["name"]), "\"Ada\"");
+    assert_eq!(format!("{:?}", &person["age"]), "36");
+    assert_eq!(format!("{:?}", &person["password"]), "\"***\"");
+
+    let scores = Scores::new(3, 1);
+    assert_eq!(scores["home"] - scores["away"], 2);
+
+    let missing = std::panic::catch_unwind(|| scores["draw"]);
+    assert!(missing.is_err());
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_serialize_only() {
+    // serializes, but has no `Deserialize` impl
+    #[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+    struct Label(&'static str);
+
+    #[dataclass(serialize_only = true)]
+    struct Metric {
+        label: Label,
+        value: u64,
+        #[field(skip, compare = false)]
+        started: std::time::Instant,
+    }
+
+    fn assert_serialize<T: serde::Serialize>(_: &T) {}
+
+    let metric = Metric::new(Label("requests"), 3, std::time::Instant::now());
+    assert_serialize(&metric);
+    assert_eq!(metric.label, Label("requests"));
+}
+
+#[test]
+fn test_deref_eq() {
+    use std::ops::Deref;
+
+    // a handle whose own equality is identity, not contents
+    #[derive(Debug, Clone)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+    struct Handle<T> {
+        id: u32,
+        value: T,
+    }
+
+    impl<T> PartialEq for Handle<T> {
+        fn eq(&self, other: &Self) -> bool {
+            self.id == other.id
+        }
+    }
+
+    impl<T> Deref for Handle<T> {
+        type Target = T;
+
+        fn deref(&self) -> &T {
+            &self.value
+        }
+    }
+
+    #[dataclass]
+    struct Document {
+        #[field(deref_eq)]
+        body: Handle<String>,
+        #[field(compare = false)]
+        handle: Handle<u8>,
+    }
+
+    let a = Document::new(
+        Handle {
+            id: 1,
+            value: "hello".to_string(),
+        },
+        Handle { id: 1, value: 0 },
+    );
+    let b = Document::new(
+        Handle {
+            id: 2,
+            value: "hello".to_string(),
+        },
+        Handle { id: 2, value: 0 },
+    );
+    let c = Document::new(
+        Handle {
+            id: 1,
+            value: "bye".to_string(),
+        },
+        Handle { id: 1, value: 0 },
+    );
+    assert_ne!(a.body, b.body);
+    assert_eq!(a, b);
+    assert_ne!(a, c);
+}
+
+#[test]
+fn test_repr_style_custom() {
+    use std::collections::HashSet;
+
+    #[dataclass(repr_style = "custom", unsafe_hash = true)]
+    struct Version {
+        major: u32,
+        minor: u32,
+    }
+
+    impl std::fmt::Debug for Version {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "v{}.{}", self.major, self.minor)
+        }
+    }
+
+    let version = Version::new(1, 2);
+    assert_eq!(format!("{:?}", version), "v1.2");
+    assert_eq!(format!("{:?}", version.get_field(1).unwrap()), "2");
+    assert_eq!(version, Version::new(1, 2));
+    let versions: HashSet<_> = [version.clone(), Version::new(1, 2)].into_iter().collect();
+    assert_eq!(versions.len(), 1);
+}
+
+#[test]
+fn test_typed_get() {
+    #[dataclass(typed_get = true)]
+    struct Settings {
+        width: i32,
+        height: i32,
+        scale: f64,
+        title: String,
+        #[field(sensitive)]
+        pin: i32,
+    }
+
+    let settings = Settings::new(640, 480, 1.5, "main".to_string(), 1234);
+    assert_eq!(settings.try_get_i32("height"), Some(480));
+    assert_eq!(settings.try_get_f64("scale"), Some(1.5));
+    assert_eq!(settings.try_get_i32("scale"), None);
+    assert_eq!(settings.try_get_i32("title"), None);
+    assert_eq!(settings.try_get_i32("pin"), None);
+}
+
+#[test]
+fn test_stable_hash() {
+    use std::collections::hash_map::DefaultHasher;
+    use std::hash::{Hash, Hasher};
+
+    #[dataclass(stable_hash = true)]
+    struct Chunk {
+        offset: u64,
+        digest: String,
+    }
+
+    fn hash_of<T: Hash>(value: &T) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        value.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    let first = hash_of(&Chunk::new(4096, "abc".to_string()));
+    let second = hash_of(&Chunk::new(4096, "abc".to_string()));
+    assert_eq!(first, second);
+
+    // the documented order: field count, then fields as declared
+    let mut hasher = DefaultHasher::new();
+    hasher.write_u64(2);
+    4096u64.hash(&mut hasher);
+    "abc".to_string().hash(&mut hasher);
+    assert_eq!(first, hasher.finish());
+}
+
+#[test]
+fn test_tiebreak() {
+    #[dataclass(order = true)]
+    struct Task {
+        priority: u8,
+        #[field(tiebreak)]
+        created: u64,
+    }
+
+    let early = Task::new(1, 100);
+    let late = Task::new(1, 200);
+    // semantically equal, yet ordered by the tiebreak field
+    assert_eq!(early, late);
+    assert!(early < late);
+    assert!(Task::new(0, 300) < early);
+
+    let mut tasks = [late, Task::new(2, 50), early];
+    tasks.sort();
+    let created: Vec<_> = tasks.iter().map(|task| task.created).collect();
+    assert_eq!(created, [100, 200, 50]);
+    assert_eq!(tasks.iter().max().unwrap().created, 50);
+    assert_eq!(tasks[..2].iter().max().unwrap().created, 200);
+}
+
+#[test]
+fn test_apply_line() {
+    #[dataclass(apply_line = true)]
+    struct Server {
+        #[field(default = String::from("localhost"))]
+        host: String,
+        #[field(default = 80)]
+        port: u16,
+        #[field(default = false)]
+        tls: bool,
+    }
+
+    let mut server = Server::new();
+    for line in [
+        "# production",
+        "host = example.com",
+        "",
+        "port=443",
+        "tls = true",
+    ] {
+        server.apply_line(line).unwrap();
+    }
+    assert_eq!(server.host, "example.com");
+    assert_eq!(server.port, 443);
+    assert!(server.tls);
+
+    assert_eq!(
+        server.apply_line("user = root").unwrap_err(),
+        "unknown field `user`"
+    );
+    assert!(server
+        .apply_line("port = https")
+        .unwrap_err()
+        .starts_with("invalid value for field `port`: "));
+    assert!(server.apply_line("port").is_err());
+    assert_eq!(server.port, 443);
+}
+
+#[test]
+fn test_apply_line_marker() {
+    struct Staging;
+
+    #[dataclass(apply_line = true)]
+    struct Settings {
+        #[field(default = 1)]
+        workers: u8,
+        stage: std::marker::PhantomData<Staging>,
+    }
+
+    let mut settings = Settings::new();
+    settings.apply_line("workers = 4").unwrap();
+    assert_eq!(settings.workers, 4);
+    assert_eq!(
+        settings.apply_line("stage = prod").unwrap_err(),
+        "unknown field `stage`"
+    );
+}
+
+#[test]
+fn test_assert_size() {
+    // a wrong size is a compile error, see the `compile_fail` doctest
+    #[dataclass(assert_size = 16)]
+    struct Header {
+        id: u64,
+        len: u32,
+        flags: u32,
+    }
+
+    assert_eq!(std::mem::size_of::<Header>(), 16);
+}
+
+#[test]
+fn test_default_const() {
+    #[dataclass(default_const = true)]
+    struct Retry {
+        #[field(default = 3)]
+        attempts: u8,
+        #[field(default = std::time::Duration::from_millis(250))]
+        delay: std::time::Duration,
+    }
+
+    const RETRY: Retry = Retry::default_const();
+    assert_eq!(RETRY.attempts, 3);
+    assert_eq!(RETRY.delay, std::time::Duration::from_millis(250));
+    assert_eq!(Retry::default(), RETRY);
+}